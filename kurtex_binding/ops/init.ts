@@ -1,11 +1,25 @@
 import type {
+    AroundEachCallback,
     CollectorRunMode,
     CreateNode,
+    EachFactory,
+    EachRow,
+    EachTable,
+    HookCallback,
+    HookCleanup,
+    Inject,
     KurtexInternals,
     KurtexPublicApi,
     LifetimeHookType,
+    Mock,
+    MockApi,
+    MockMatchers,
+    MockState,
     ObjectEntry,
+    Procedure,
+    TaskArgument,
     TaskFactory,
+    TaskOptions,
     Test,
     TestCallback,
     TestFactory
@@ -14,12 +28,35 @@ import type {
 const { core } = Deno
 const { ops } = core
 
+// Both `test(name, fn, options)` and `test(name, options, fn)`. Only
+// plain objects are options, a numeric timeout is dropped.
+function taskArguments(
+    fnOrOptions?: TaskArgument,
+    optionsOrFn?: TaskArgument
+): [TestCallback | undefined, TaskOptions | undefined] {
+    const args = [fnOrOptions, optionsOrFn]
+    const callback = args.find(arg => typeof arg === 'function')
+    // `typeof null` is 'object' too.
+    const options = args.find(
+        arg => arg !== null && typeof arg === 'object' && !Array.isArray(arg)
+    )
+
+    return [callback as TestCallback, options as TaskOptions]
+}
+
 function registerTaskImpl(runMode: CollectorRunMode): TaskFactory {
-    return (identifier: string, callback: TestCallback | undefined) => {
+    return (
+        identifier: string,
+        fnOrOptions?: TaskArgument,
+        optionsOrFn?: TaskArgument
+    ) => {
+        const [callback, options] = taskArguments(fnOrOptions, optionsOrFn)
+
         kurtexInternals.registerCollectorTask(
             identifier,
             callback && runMode !== 'todo' ? callback : () => {},
-            runMode
+            runMode,
+            options ?? {}
         )
     }
 }
@@ -34,38 +71,651 @@ function registerNodeImpl(runMode: CollectorRunMode) {
     }
 }
 
+function registerEachImpl(
+    register: (identifier: string, fn?: () => unknown) => void
+): EachFactory {
+    return ((table: EachTable, ...templateValues: unknown[]) => {
+        const rows = parseEachTable(table, templateValues)
+
+        return (name: string, fn?: (...args: any[]) => unknown) => {
+            rows.forEach((row, index) => {
+                const args = Array.isArray(row) ? row : [row]
+
+                register(
+                    formatEachTitle(name, args, index),
+                    fn && (() => fn(...args))
+                )
+            })
+        }
+    }) as EachFactory
+}
+
+// Tagged template tables start with a `a | b` heading row,
+// every following group of values becomes an object row.
+function parseEachTable(
+    table: EachTable,
+    templateValues: unknown[]
+): EachRow[] {
+    if (!('raw' in table)) {
+        return [...table] as EachRow[]
+    }
+
+    const headings = table[0]
+        .split('|')
+        .map(heading => heading.trim())
+        .filter(Boolean)
+
+    if (headings.length === 0 || templateValues.length % headings.length) {
+        throw new Error(
+            `Table has ${templateValues.length} values for ${headings.length} columns.`
+        )
+    }
+
+    const rows: EachRow[] = []
+
+    for (let i = 0; i < templateValues.length; i += headings.length) {
+        const row: Record<string, unknown> = {}
+
+        headings.forEach((heading, column) => {
+            row[heading] = templateValues[i + column]
+        })
+        rows.push(row)
+    }
+
+    return rows
+}
+
+function formatEachValue(value: unknown): string {
+    if (typeof value === 'string') return value
+    if (typeof value === 'function') return `[Function ${value.name}]`
+
+    try {
+        return JSON.stringify(value) ?? String(value)
+    } catch {
+        return String(value)
+    }
+}
+
+function formatEachTitle(name: string, args: unknown[], index: number) {
+    let argIndex = 0
+    const nextArg = () => args[argIndex++]
+
+    let title = name.replace(/%[sdifjoO#%]/g, specifier => {
+        switch (specifier) {
+            case '%%':
+                return '%'
+            case '%#':
+                return String(index)
+            case '%s':
+                return String(nextArg())
+            case '%d':
+                return String(Number(nextArg()))
+            case '%i':
+                return String(Math.trunc(Number(nextArg())))
+            case '%f':
+                return String(parseFloat(String(nextArg())))
+            default:
+                return formatEachValue(nextArg())
+        }
+    })
+
+    const [row] = args
+
+    if (args.length === 1 && row !== null && typeof row === 'object') {
+        title = title.replace(/\$([\w.]+)/g, (match, path: string) => {
+            const value = path
+                .split('.')
+                .reduce<any>((object, key) => object?.[key], row)
+
+            return value === undefined ? match : formatEachValue(value)
+        })
+    }
+
+    return title
+}
+
+type FixtureMap = Record<string, unknown>
+
+// Splits on commas outside of nested brackets, braces and parens.
+function splitTopLevel(source: string): string[] {
+    const parts: string[] = []
+    let depth = 0
+    let start = 0
+
+    for (let i = 0; i < source.length; i++) {
+        const char = source[i]
+
+        if ('([{'.includes(char)) depth++
+        else if (')]}'.includes(char)) depth--
+        else if (char === ',' && depth === 0) {
+            parts.push(source.slice(start, i))
+            start = i + 1
+        }
+    }
+
+    return [...parts, source.slice(start)]
+}
+
+// Fixtures destructured in the first parameter, e.g. `({ db }) => {}`,
+// a rest element uses all of them.
+function usedFixtures(fn: Function, fixtures: FixtureMap): string[] {
+    const source = fn.toString()
+    const paramsStart = source.indexOf('(')
+
+    if (paramsStart === -1 || source.slice(0, paramsStart).includes('=>')) {
+        return []
+    }
+
+    const params = source.slice(paramsStart + 1).trimStart()
+
+    if (!params.startsWith('{')) {
+        return []
+    }
+
+    let depth = 0
+    let end = 0
+
+    do {
+        if (params[end] === '{') depth++
+        else if (params[end] === '}') depth--
+        end++
+    } while (depth > 0 && end < params.length)
+
+    const names = splitTopLevel(params.slice(1, end - 1))
+        .map(property => property.trim())
+        .filter(Boolean)
+
+    if (names.some(name => name.startsWith('...'))) {
+        return Object.keys(fixtures)
+    }
+
+    return names
+        .map(name => name.split(/[:=]/)[0].trim())
+        .filter(name => name in fixtures)
+}
+
+// Initializes the fixtures a test uses, and the fixtures those
+// depend on, then tears them down in reverse order after the test.
+async function runWithFixtures(
+    fixtures: FixtureMap,
+    context: Record<string, unknown>,
+    callback: TestCallback<any>
+) {
+    const initialized = new Map<string, Promise<unknown>>()
+    const cleanups: (() => Promise<void>)[] = []
+
+    const initFixture = (name: string, path: string[]): Promise<unknown> => {
+        if (path.includes(name)) {
+            const cycle = [...path, name].join(' -> ')
+
+            return Promise.reject(
+                new Error(`Circular fixture dependency: ${cycle}`)
+            )
+        }
+
+        if (!initialized.has(name)) {
+            initialized.set(name, setupFixture(name, [...path, name]))
+        }
+
+        return initialized.get(name)!
+    }
+
+    const setupFixture = async (name: string, path: string[]) => {
+        const fixture = fixtures[name]
+
+        if (typeof fixture !== 'function') {
+            return fixture
+        }
+
+        for (const dependency of usedFixtures(fixture, fixtures)) {
+            context[dependency] = await initFixture(dependency, path)
+        }
+
+        return new Promise((provideValue, failSetup) => {
+            let finishUse = () => {}
+            const fixtureDone = Promise.resolve().then(() =>
+                fixture(context, (value: unknown) => {
+                    provideValue(value)
+
+                    return new Promise<void>(resolve => {
+                        finishUse = resolve
+                    })
+                })
+            )
+
+            cleanups.push(async () => {
+                finishUse()
+                await fixtureDone
+            })
+
+            fixtureDone.then(
+                () => failSetup(new Error(`Fixture "${name}" did not call use().`)),
+                failSetup
+            )
+        })
+    }
+
+    let testError: unknown
+
+    try {
+        for (const name of usedFixtures(callback, fixtures)) {
+            context[name] = await initFixture(name, [])
+        }
+
+        await callback(context as any)
+    } catch (error) {
+        testError = error
+    }
+
+    let teardownError: unknown
+
+    for (const cleanup of cleanups.reverse()) {
+        await cleanup().catch(error => {
+            teardownError ??= error
+        })
+    }
+
+    if (testError !== undefined && teardownError !== undefined) {
+        throw combineErrors(testError, teardownError)
+    }
+
+    if (testError !== undefined || teardownError !== undefined) {
+        throw testError ?? teardownError
+    }
+}
+
+// Keeps the test error first and appends the teardown error, the
+// same way the runner reports an afterEach error after a failure.
+function combineErrors(error: unknown, teardownError: unknown): Error {
+    const describe = (value: unknown) =>
+        value instanceof Error ? value.stack ?? String(value) : String(value)
+    const combined = new Error(
+        `${describe(error)}\n\n${describe(teardownError)}`
+    )
+    combined.stack = combined.message
+
+    return combined
+}
+
+function extendTest(fixtures: FixtureMap): Test<any> {
+    const withFixtures =
+        (register: TaskFactory): TaskFactory =>
+        (
+            identifier: string,
+            fnOrOptions?: TaskArgument,
+            optionsOrFn?: TaskArgument
+        ) => {
+            const [callback, options] = taskArguments(fnOrOptions, optionsOrFn)
+
+            register(
+                identifier,
+                callback &&
+                    (context => runWithFixtures(fixtures, context, callback)),
+                options
+            )
+        }
+
+    const extended = withFixtures(registerTask) as Test<any>
+    extended.only = withFixtures(registerTask.only) as Test<any>['only']
+    extended.skip = withFixtures(registerTask.skip) as Test<any>['skip']
+    extended.todo = registerTask.todo
+    extended.fails = withFixtures(registerTask.fails)
+    extended.skipIf = (condition: unknown) =>
+        condition ? extended.skip : extended
+    extended.runIf = (condition: unknown) =>
+        condition ? extended : extended.skip
+
+    // Each rows are passed in place of the context, so no fixtures
+    // are destructured and none get set up for them.
+    extended.each = registerEachImpl(extended)
+    extended.only.each = registerEachImpl(extended.only)
+    extended.skip.each = registerEachImpl(extended.skip)
+    extended.extend = (more: FixtureMap) =>
+        extendTest({ ...fixtures, ...more })
+
+    return extended
+}
+
 function registerLifetimeHookImpl(hook: LifetimeHookType) {
-    return (callback: TestCallback) => {
+    return (callback: HookCallback) => {
         kurtexInternals.registerLifetimeHook(hook, callback)
     }
 }
 
+// A `beforeEach` that resolves once `runTest` is called, and a
+// cleanup that lets `runTest` resolve and waits for the wrapper.
+// Hooks have no timeout, a callback that never calls `runTest` and
+// never settles keeps the run waiting.
+function aroundEachHook(callback: AroundEachCallback) {
+    beforeEachHook(
+        () =>
+            new Promise<HookCleanup>((started, failed) => {
+                let testDone: Promise<void> | undefined
+                const runTest = () =>
+                    (testDone ??= new Promise<void>(finishTest => {
+                        started(async () => {
+                            finishTest()
+                            await wrapper
+                        })
+                    }))
+                const wrapper = Promise.resolve().then(() =>
+                    callback(runTest)
+                )
+
+                wrapper.then(
+                    () =>
+                        failed(
+                            new Error('aroundEach callback did not call runTest().')
+                        ),
+                    failed
+                )
+            })
+    )
+}
+
+// Mocks of every test file, `clearMocks` and friends only touch
+// the mocks of the file that is running.
+const mockRegistry = new Map<string, Set<Mock>>()
+let mockFile = ''
+
+function currentMocks() {
+    let mocks = mockRegistry.get(mockFile)
+
+    if (!mocks) {
+        mocks = new Set()
+        mockRegistry.set(mockFile, mocks)
+    }
+
+    return mocks
+}
+
+function createMock(implementation?: Procedure, restore?: () => void) {
+    const state: MockState<Procedure> = {
+        calls: [],
+        results: [],
+        contexts: [],
+        get lastCall() {
+            return this.calls[this.calls.length - 1]
+        }
+    }
+    let currentImplementation = implementation
+    let onceImplementations: Procedure[] = []
+
+    const mock = function (this: unknown, ...args: unknown[]) {
+        state.calls.push(args)
+        state.contexts.push(this)
+
+        const callImplementation =
+            onceImplementations.shift() ?? currentImplementation
+
+        try {
+            const value = callImplementation?.apply(this, args)
+            state.results.push({ type: 'return', value })
+
+            return value
+        } catch (error) {
+            state.results.push({ type: 'throw', value: error })
+            throw error
+        }
+    } as Mock
+
+    mock.mock = state
+    mock.mockImplementation = fn => {
+        currentImplementation = fn
+        return mock
+    }
+    mock.mockImplementationOnce = fn => {
+        onceImplementations.push(fn)
+        return mock
+    }
+    mock.mockReturnValue = value => mock.mockImplementation(() => value)
+    mock.mockReturnValueOnce = value =>
+        mock.mockImplementationOnce(() => value)
+    mock.mockResolvedValue = value =>
+        mock.mockImplementation(() => Promise.resolve(value))
+    mock.mockResolvedValueOnce = value =>
+        mock.mockImplementationOnce(() => Promise.resolve(value))
+    mock.mockRejectedValue = error =>
+        mock.mockImplementation(() => Promise.reject(error))
+    mock.mockRejectedValueOnce = error =>
+        mock.mockImplementationOnce(() => Promise.reject(error))
+    mock.mockClear = () => {
+        state.calls = []
+        state.results = []
+        state.contexts = []
+        return mock
+    }
+    mock.mockReset = () => {
+        currentImplementation = implementation
+        onceImplementations = []
+        return mock.mockClear()
+    }
+    mock.mockRestore = () => {
+        mock.mockReset()
+
+        if (restore) {
+            restore()
+            mocks.delete(mock)
+        }
+    }
+
+    const mocks = currentMocks()
+
+    Object.defineProperty(mock, '_isMockFunction', { value: true })
+    mocks.add(mock)
+
+    return mock
+}
+
+const kurtexMockApi: MockApi = {
+    fn: (implementation?: Procedure) => createMock(implementation) as any,
+    spyOn(object: any, method: PropertyKey) {
+        const original = object[method]
+
+        if (typeof original !== 'function') {
+            throw new TypeError(`Cannot spy on ${String(method)}, it is not a function.`)
+        }
+
+        if (kurtexMockApi.isMockFunction(original)) {
+            return original
+        }
+
+        const spy = createMock(original, () => {
+            object[method] = original
+        })
+        object[method] = spy
+
+        return spy as any
+    },
+    isMockFunction: (value: unknown): value is Mock =>
+        typeof value === 'function' && '_isMockFunction' in value,
+    clearAllMocks: () => currentMocks().forEach(mock => mock.mockClear()),
+    resetAllMocks: () => currentMocks().forEach(mock => mock.mockReset()),
+    restoreAllMocks: () =>
+        currentMocks().forEach(mock => mock.mockRestore()),
+    // Only mock matchers for now.
+    expect: actual => ({
+        ...createMockMatchers(actual, false),
+        not: createMockMatchers(actual, true)
+    })
+}
+
+function isDeepEqual(a: unknown, b: unknown): boolean {
+    if (Object.is(a, b)) return true
+    if (typeof a !== 'object' || typeof b !== 'object' || !a || !b) {
+        return false
+    }
+    if (Array.isArray(a) !== Array.isArray(b)) return false
+    if (Object.getPrototypeOf(a) !== Object.getPrototypeOf(b)) return false
+    if (a instanceof Date) return a.getTime() === (b as Date).getTime()
+
+    if (a instanceof Map) {
+        const other = b as Map<unknown, unknown>
+
+        return (
+            a.size === other.size &&
+            [...a].every(
+                ([key, value]) =>
+                    other.has(key) && isDeepEqual(value, other.get(key))
+            )
+        )
+    }
+
+    if (a instanceof Set) {
+        const other = [...(b as Set<unknown>)]
+
+        return (
+            a.size === other.length &&
+            [...a].every(value =>
+                other.some(otherValue => isDeepEqual(value, otherValue))
+            )
+        )
+    }
+
+    // Typed arrays and DataView, compared byte by byte.
+    if (ArrayBuffer.isView(a)) {
+        const other = b as ArrayBufferView
+        const bytes = new Uint8Array(a.buffer, a.byteOffset, a.byteLength)
+        const otherBytes = new Uint8Array(
+            other.buffer,
+            other.byteOffset,
+            other.byteLength
+        )
+
+        return (
+            bytes.length === otherBytes.length &&
+            bytes.every((byte, index) => byte === otherBytes[index])
+        )
+    }
+
+    const keys = Object.keys(a)
+
+    return (
+        keys.length === Object.keys(b).length &&
+        keys.every(key => isDeepEqual((a as any)[key], (b as any)[key]))
+    )
+}
+
+function formatCall(args: unknown[]) {
+    return `(${args.map(formatEachValue).join(', ')})`
+}
+
+function createMockMatchers(actual: unknown, negated: boolean): MockMatchers {
+    const mockState = () => {
+        if (!kurtexMockApi.isMockFunction(actual)) {
+            throw new TypeError(`${formatEachValue(actual)} is not a mock function.`)
+        }
+
+        return actual.mock
+    }
+
+    const assert = (pass: boolean, message: string) => {
+        if (pass === negated) {
+            throw new Error(`Expected mock ${negated ? 'not ' : ''}${message}`)
+        }
+    }
+
+    const received = (state: MockState<Procedure>) =>
+        state.calls.length
+            ? `, received calls: ${state.calls.map(formatCall).join(', ')}`
+            : ', it was never called'
+
+    return {
+        toHaveBeenCalled() {
+            const { calls } = mockState()
+            assert(calls.length > 0, `to have been called, called ${calls.length} times.`)
+        },
+        toHaveBeenCalledTimes(times: number) {
+            const { calls } = mockState()
+            assert(
+                calls.length === times,
+                `to have been called ${times} times, called ${calls.length} times.`
+            )
+        },
+        toHaveBeenCalledWith(...args: unknown[]) {
+            const state = mockState()
+            assert(
+                state.calls.some(call => isDeepEqual(call, args)),
+                `to have been called with ${formatCall(args)}${received(state)}.`
+            )
+        },
+        toHaveBeenLastCalledWith(...args: unknown[]) {
+            const state = mockState()
+            assert(
+                isDeepEqual(state.lastCall, args),
+                `to have been last called with ${formatCall(args)}${received(state)}.`
+            )
+        },
+        toHaveReturnedWith(value: unknown) {
+            const { results } = mockState()
+            assert(
+                results.some(
+                    result =>
+                        result.type === 'return' &&
+                        isDeepEqual(result.value, value)
+                ),
+                `to have returned ${formatEachValue(value)}.`
+            )
+        }
+    }
+}
+
 const registerTask = registerTaskImpl('run') as Test
 registerTask.only = registerTaskImpl('only')
 registerTask.skip = registerTaskImpl('skip')
 registerTask.todo = registerTaskImpl('todo')
+registerTask.fails = registerTaskImpl('fails')
+registerTask.skipIf = (condition: unknown) =>
+    condition ? registerTask.skip : registerTask
+registerTask.runIf = (condition: unknown) =>
+    condition ? registerTask : registerTask.skip
+registerTask.extend = (fixtures: FixtureMap) => extendTest(fixtures)
 
 const registerNode = registerNodeImpl('run') as CreateNode
 
 registerNode.only = registerNodeImpl('only')
 registerNode.skip = registerNodeImpl('skip')
 registerNode.todo = registerNodeImpl('todo')
+registerNode.skipIf = (condition: unknown) =>
+    condition ? registerNode.skip : registerNode
+registerNode.runIf = (condition: unknown) =>
+    condition ? registerNode : registerNode.skip
+
+registerTask.each = registerEachImpl(registerTask)
+registerTask.only.each = registerEachImpl(registerTask.only)
+registerTask.skip.each = registerEachImpl(registerTask.skip)
+
+registerNode.each = registerEachImpl(registerNode)
+registerNode.only.each = registerEachImpl(registerNode.only)
+registerNode.skip.each = registerEachImpl(registerNode.skip)
 
 const beforeAllHook = registerLifetimeHookImpl('beforeAll')
 const afterAllHook = registerLifetimeHookImpl('afterAll')
 const beforeEachHook = registerLifetimeHookImpl('beforeEach')
 const afterEachHook = registerLifetimeHookImpl('afterEach')
 
+const inject: Inject = key => ops.op_inject(key) ?? undefined
+
 const kurtexInternals = {
-    registerCollectorTask(identifier, callback, mode) {
-        ops.op_register_collector_task(identifier, callback, mode)
+    registerCollectorTask(identifier, callback, mode, options) {
+        ops.op_register_collector_task(identifier, callback, mode, options)
     },
     registerCollectorNode(identifier, factory, mode) {
         ops.op_register_collector_node(identifier, factory, mode)
     },
     registerLifetimeHook(hook, callback) {
         ops.op_register_lifetime_hook(hook, callback)
-    }
+    },
+    setMockFile(file, reset) {
+        mockFile = file
+
+        if (reset) {
+            mockRegistry.delete(file)
+        }
+    },
+    clearAllMocks: kurtexMockApi.clearAllMocks,
+    resetAllMocks: kurtexMockApi.resetAllMocks,
+    restoreAllMocks: kurtexMockApi.restoreAllMocks
 } satisfies KurtexInternals
 
 const kurtexPublicApi = {
@@ -77,7 +727,10 @@ const kurtexPublicApi = {
     beforeAll: beforeAllHook,
     afterAll: afterAllHook,
     beforeEach: beforeEachHook,
-    afterEach: afterEachHook
+    afterEach: afterEachHook,
+    aroundEach: aroundEachHook,
+    inject,
+    kurtex: kurtexMockApi
 } satisfies KurtexPublicApi
 
 function registerApiGlobally() {
@@ -1,31 +1,177 @@
 type Awaitable<T> = T | Promise<T>
 
-export type TestCallback = () => Awaitable<void>
+// First argument of every test callback.
+export interface TestContext {
+  task: { name: string }
+}
+
+export type TestCallback<Context = {}> = (
+  context: TestContext & Context
+) => Awaitable<void>
 export type TestFactory = () => Awaitable<void>
 
-export interface TaskFactory {
-  (identifier: string, fn?: TestCallback): void
+// A fixture is either a plain value or a function that passes the
+// value to `use` and cleans up once `use` resolves after the test.
+export type FixtureFn<Value, Context> = (
+  context: TestContext & Context,
+  use: (value: Value) => Promise<void>
+) => Awaitable<void>
+
+export type Fixtures<Extra, Context = {}> = {
+  [Key in keyof Extra]:
+    | Extra[Key]
+    | FixtureFn<Extra[Key], Context & Extra>
+}
+
+export interface TaskOptions {
+  // Reruns the test up to this many times when it fails.
+  retry?: number
+  // Runs the test this many times and reports it as flaky when
+  // only some of the runs pass.
+  repeats?: number
+}
+
+// Other runners take a timeout in milliseconds in place of the
+// options, it is accepted and ignored.
+export type TaskArgument = TestCallback<any> | TaskOptions | number
+
+export interface TaskFactory<Context = {}> {
+  (
+    identifier: string,
+    fn?: TestCallback<Context>,
+    options?: TaskOptions | number
+  ): void
+  (
+    identifier: string,
+    options: TaskOptions,
+    fn?: TestCallback<Context>
+  ): void
 }
 
 export interface CreateNodeFactory {
   (identifier: string): void
 }
 
-export interface Test extends TaskFactory {
-  skip: TaskFactory
-  only: TaskFactory
-  todo: TaskFactory
+export type EachRow = unknown
+export type EachTable = readonly EachRow[] | TemplateStringsArray
+
+type EachCallback<T> = T extends readonly unknown[]
+  ? (...args: T) => Awaitable<void>
+  : (arg: T) => Awaitable<void>
+
+export interface EachFactory {
+  <T>(table: readonly T[]): (name: string, fn?: EachCallback<T>) => void
+  (
+    strings: TemplateStringsArray,
+    ...values: unknown[]
+  ): (name: string, fn?: (row: any) => Awaitable<void>) => void
+}
+
+export interface Test<Context = {}> extends TaskFactory<Context> {
+  skip: TaskFactory<Context> & { each: EachFactory }
+  only: TaskFactory<Context> & { each: EachFactory }
+  todo: TaskFactory<Context>
+  fails: TaskFactory<Context>
+  // Rows are passed instead of the context, fixtures are not set up.
+  each: EachFactory
+  skipIf: (condition: unknown) => TaskFactory<Context>
+  runIf: (condition: unknown) => TaskFactory<Context>
+  extend: <Extra extends object>(
+    fixtures: Fixtures<Extra, Context>
+  ) => Test<Context & Extra>
 }
 
 export interface CreateNode extends CreateNodeFactory {
-  skip: CreateNodeFactory
-  only: CreateNodeFactory
+  skip: CreateNodeFactory & { each: EachFactory }
+  only: CreateNodeFactory & { each: EachFactory }
   todo: CreateNodeFactory
+  each: EachFactory
+  skipIf: (condition: unknown) => CreateNodeFactory
+  runIf: (condition: unknown) => CreateNodeFactory
+}
+
+// `beforeAll` and `beforeEach` may return a cleanup function,
+// it runs after the matching `afterAll` / `afterEach` hooks.
+export type HookCleanup = () => Awaitable<void>
+export type HookCallback = () => Awaitable<void | HookCleanup>
+export type LifetimeHook = (callback: HookCallback) => void
+
+// Wraps every test, `runTest` resolves once the test finished.
+// The callback has no timeout, it must call `runTest` or settle.
+export type AroundEachCallback = (
+  runTest: () => Promise<void>
+) => Awaitable<void>
+export type AroundEachHook = (callback: AroundEachCallback) => void
+
+// Argument of the `setup` export of a `globalSetup` module.
+export interface GlobalSetupContext {
+  // Values have to be JSON serializable.
+  provide(key: string, value: unknown): void
+}
+
+// Reads a value passed to `provide` in a `globalSetup` module.
+export type Inject = <T = unknown>(key: string) => T | undefined
+
+export type Procedure = (...args: any[]) => any
+
+export interface MockResult {
+  type: 'return' | 'throw'
+  value: unknown
+}
+
+export interface MockState<T extends Procedure> {
+  calls: Parameters<T>[]
+  results: MockResult[]
+  // `this` of every call.
+  contexts: unknown[]
+  lastCall: Parameters<T> | undefined
+}
+
+export interface Mock<T extends Procedure = Procedure> {
+  (...args: Parameters<T>): ReturnType<T>
+  mock: MockState<T>
+  mockImplementation(fn: T): this
+  mockImplementationOnce(fn: T): this
+  mockReturnValue(value: ReturnType<T>): this
+  mockReturnValueOnce(value: ReturnType<T>): this
+  mockResolvedValue(value: Awaited<ReturnType<T>>): this
+  mockResolvedValueOnce(value: Awaited<ReturnType<T>>): this
+  mockRejectedValue(error: unknown): this
+  mockRejectedValueOnce(error: unknown): this
+  // Forgets recorded calls.
+  mockClear(): this
+  // Also drops implementations set after creation.
+  mockReset(): this
+  // Also puts the original method back on spies.
+  mockRestore(): void
+}
+
+export interface MockApi {
+  fn<T extends Procedure = Procedure>(implementation?: T): Mock<T>
+  spyOn<O extends object, K extends keyof O>(
+    object: O,
+    method: K
+  ): O[K] extends Procedure ? Mock<O[K]> : never
+  isMockFunction(value: unknown): value is Mock
+  clearAllMocks(): void
+  resetAllMocks(): void
+  restoreAllMocks(): void
+  expect: Expect
+}
+
+export interface MockMatchers {
+  toHaveBeenCalled(): void
+  toHaveBeenCalledTimes(times: number): void
+  toHaveBeenCalledWith(...args: unknown[]): void
+  toHaveBeenLastCalledWith(...args: unknown[]): void
+  toHaveReturnedWith(value: unknown): void
 }
 
-export type LifetimeHook = (callback: TestCallback) => void
+export type Expect = (
+  actual: unknown
+) => MockMatchers & { not: MockMatchers }
 
-export type CollectorRunMode = 'run' | 'skip' | 'only' | 'todo'
+export type CollectorRunMode = 'run' | 'skip' | 'only' | 'todo' | 'fails'
 export type LifetimeHookType =
   | 'beforeAll'
   | 'afterAll'
@@ -35,7 +181,8 @@ export type LifetimeHookType =
 type RegisterCollectorTask = (
   identifier: string,
   callback: TestCallback,
-  mode: CollectorRunMode
+  mode: CollectorRunMode,
+  options: TaskOptions
 ) => void
 
 type RegisterCollectorNode = (
@@ -46,13 +193,21 @@ type RegisterCollectorNode = (
 
 type RegisterLifetimeHook = (
   hook: LifetimeHookType,
-  callback: TestCallback
+  callback: HookCallback
 ) => void
 
 export interface KurtexInternals {
   registerCollectorTask: RegisterCollectorTask
   registerCollectorNode: RegisterCollectorNode
   registerLifetimeHook: RegisterLifetimeHook
+  // Called by the runner when a test file is evaluated (`reset`
+  // drops its previous mocks) and before its tests run.
+  setMockFile: (file: string, reset: boolean) => void
+  // Called by the runner before each test per `clearMocks`,
+  // `resetMocks` and `restoreMocks`.
+  clearAllMocks: () => void
+  resetAllMocks: () => void
+  restoreAllMocks: () => void
 }
 
 export interface KurtexPublicApi {
@@ -65,6 +220,9 @@ export interface KurtexPublicApi {
   afterAll: LifetimeHook
   beforeEach: LifetimeHook
   afterEach: LifetimeHook
+  aroundEach: AroundEachHook
+  inject: Inject
+  kurtex: MockApi
 }
 
 export type ObjectEntry<T> = {
@@ -35,6 +35,24 @@ impl TypescriptModuleLoader {
   pub fn graph_loader(&self) -> &RcCell<GraphMemoryLoader> {
     &self.graph_loader
   }
+
+  // Maps a 1-based position in emitted code back to the original
+  // source. Modules loaded without transpiling have no source map.
+  pub fn original_position(
+    &self,
+    specifier: &str,
+    line: u32,
+    column: u32,
+  ) -> Option<(u32, u32)> {
+    let source_maps = self.source_maps.borrow();
+    let source_map = source_maps.get(specifier)?;
+    let source_map =
+      sourcemap::SourceMap::from_slice(source_map).ok()?;
+    let token = source_map
+      .lookup_token(line.saturating_sub(1), column.saturating_sub(1))?;
+
+    Some((token.get_src_line() + 1, token.get_src_col() + 1))
+  }
 }
 
 impl deno_core::ModuleLoader for TypescriptModuleLoader {
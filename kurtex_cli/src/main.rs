@@ -1,10 +1,15 @@
 use std::env;
+use std::path::PathBuf;
 
 use anyhow::Context;
 use clap::builder::Command;
 use clap::{Arg, ArgAction};
+use regex::Regex;
 use tracing_subscriber::filter::FilterExt;
 
+use kurtex_core::runner::shard::Shard;
+use kurtex_core::{HookSequence, ReporterKind};
+
 use crate::result::CliResult;
 use crate::runner::{CliRunner, Runner};
 
@@ -47,6 +52,7 @@ fn build_cli() -> Command {
         .long("root")
         .value_name("ROOT_DIR")
         .help("Root path")
+        .global(true)
         .require_equals(true)
         .value_hint(clap::ValueHint::DirPath)
         .value_parser(clap::value_parser!(String)),
@@ -56,11 +62,22 @@ fn build_cli() -> Command {
         .long("config")
         .short('c')
         .help("Path to config file")
+        .global(true)
         .default_value("./kurtex.config.ts")
         .require_equals(true)
         .value_hint(clap::ValueHint::FilePath)
         .value_parser(clap::value_parser!(String)),
     )
+    .arg(
+      Arg::new("reporter")
+        .long("reporter")
+        .value_name("NAME")
+        .help("Reporter to use: default, json or blob, can be repeated")
+        .global(true)
+        .require_equals(true)
+        .action(ArgAction::Append)
+        .value_parser(|reporter: &str| reporter.parse::<ReporterKind>()),
+    )
     .arg(
       Arg::new("watch")
         .long("watch")
@@ -83,12 +100,182 @@ fn build_cli() -> Command {
         .help("Run tasks in parallel")
         .value_parser(clap::value_parser!(bool)),
     )
+    .arg(
+      Arg::new("filters")
+        .value_name("FILTERS")
+        .help("Only run test files whose path contains or matches FILTERS, use file:line to run the test at a line")
+        .num_args(0..)
+        .value_hint(clap::ValueHint::FilePath)
+        .value_parser(clap::value_parser!(String)),
+    )
+    .arg(
+      Arg::new("test-name-pattern")
+        .long("test-name-pattern")
+        .short('t')
+        .value_name("PATTERN")
+        .help("Only run tests whose full name matches the regex PATTERN")
+        .value_parser(Regex::new),
+    )
+    .arg(
+      Arg::new("shard")
+        .long("shard")
+        .value_name("INDEX/COUNT")
+        .help("Only run the INDEX-th of COUNT slices of the test files")
+        .require_equals(true)
+        .value_parser(|shard: &str| shard.parse::<Shard>()),
+    )
+    .arg(
+      Arg::new("durations")
+        .long("durations")
+        .value_name("PATH")
+        .help("Record test file durations to PATH and balance shards by them")
+        .require_equals(true)
+        .value_hint(clap::ValueHint::FilePath)
+        .value_parser(clap::value_parser!(PathBuf)),
+    )
+    .arg(
+      Arg::new("retry")
+        .long("retry")
+        .value_name("TIMES")
+        .help("Retry failed tests up to TIMES times")
+        .require_equals(true)
+        .value_parser(clap::value_parser!(u32)),
+    )
+    .arg(
+      Arg::new("repeat")
+        .long("repeat")
+        .value_name("TIMES")
+        .help("Run every test TIMES times and report flaky ones")
+        .require_equals(true)
+        .value_parser(clap::value_parser!(u32)),
+    )
+    .arg(
+      Arg::new("bail")
+        .long("bail")
+        .value_name("FAILURES")
+        .help("Stop running tests after FAILURES failed tests, 1 by default")
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_missing_value("1")
+        .value_parser(clap::value_parser!(u32).range(1..)),
+    )
+    .arg(
+      Arg::new("passWithNoTests")
+        .long("passWithNoTests")
+        .action(ArgAction::SetTrue)
+        .help("Exit successfully when no tests are found"),
+    )
+    .arg(
+      Arg::new("sequence.shuffle")
+        .long("sequence.shuffle")
+        .action(ArgAction::SetTrue)
+        .help("Run files, suites and tests in random order"),
+    )
+    .arg(
+      Arg::new("sequence.seed")
+        .long("sequence.seed")
+        .value_name("SEED")
+        .help("Seed of the random order, printed after each shuffled run")
+        .require_equals(true)
+        .value_parser(clap::value_parser!(u64)),
+    )
+    .arg(
+      Arg::new("sequence.hooks")
+        .long("sequence.hooks")
+        .value_name("ORDER")
+        .help("Run hooks in list, stack (after hooks reversed) or parallel order")
+        .require_equals(true)
+        .value_parser(|order: &str| order.parse::<HookSequence>()),
+    )
+    .arg(
+      Arg::new("changed")
+        .long("changed")
+        .value_name("REF")
+        .help("Run tests related to uncommitted changes, or changes since REF with --changed=REF")
+        .num_args(0..=1)
+        .require_equals(true)
+        .default_missing_value("")
+        .value_parser(clap::value_parser!(String)),
+    )
+    .subcommand(
+      Command::new("bisect")
+        .about("Find the earlier test that makes a test fail in the full run")
+        .arg(
+          Arg::new("test")
+            .value_name("TEST_ID")
+            .help("Failing test, e.g. 'tests/math.test.ts > math > sum'")
+            .required(true)
+            .value_parser(clap::value_parser!(String)),
+        )
+        .arg(
+          Arg::new("sequence.shuffle")
+            .long("sequence.shuffle")
+            .action(ArgAction::SetTrue)
+            .requires("sequence.seed")
+            .help("Bisect the shuffled order of --sequence.seed"),
+        )
+        .arg(
+          Arg::new("sequence.seed")
+            .long("sequence.seed")
+            .value_name("SEED")
+            .help("Seed printed by the failing shuffled run")
+            .require_equals(true)
+            .value_parser(clap::value_parser!(u64)),
+        ),
+    )
+    .subcommand(
+      Command::new("list")
+        .about("Collect test files and list tests without running them")
+        .arg(
+          Arg::new("json")
+            .long("json")
+            .action(ArgAction::SetTrue)
+            .help("Print results as JSON"),
+        ),
+    )
+    .subcommand(
+      Command::new("merge-reports")
+        .about("Merge blob reports of sharded runs into a single report")
+        .arg(
+          Arg::new("dir")
+            .value_name("DIR")
+            .required(true)
+            .value_hint(clap::ValueHint::DirPath)
+            .value_parser(clap::value_parser!(PathBuf)),
+        ),
+    )
+    .subcommand(
+      Command::new("related")
+        .about("List test files that depend on the given source files")
+        .arg(
+          Arg::new("paths")
+            .value_name("PATHS")
+            .required(true)
+            .num_args(1..)
+            .value_hint(clap::ValueHint::FilePath)
+            .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+          Arg::new("why")
+            .long("why")
+            .action(ArgAction::SetTrue)
+            .help("Print the import chain from each test to the source"),
+        )
+        .arg(
+          Arg::new("json")
+            .long("json")
+            .action(ArgAction::SetTrue)
+            .help("Print results as JSON"),
+        ),
+    )
 }
 
 pub mod exits {
-  #[allow(unused)]
   pub const SUCCESS: i32 = 0;
-  pub const RUNTIME_ERROR: i32 = 1;
+  pub const TESTS_FAILED: i32 = 1;
+  pub const COLLECTION_ERROR: i32 = 2;
+  pub const NO_TESTS_FOUND: i32 = 3;
+  pub const INTERNAL_ERROR: i32 = 4;
 }
 
 pub mod settings {
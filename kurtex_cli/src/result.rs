@@ -1,13 +1,136 @@
 use std::process::ExitCode;
 
+use nu_ansi_term::Color;
+
+use kurtex_core::runner::summary::RunSummary;
+use kurtex_core::{AnyError, AnyResult};
+
+use crate::exits;
+
 #[derive(Debug)]
 pub enum CliResult {
   None,
+  // A test run that went through, failed tests included.
+  Finished { summary: RunSummary, pass_with_no_tests: bool },
+  // The config file could not be loaded.
+  ConfigError(AnyError),
+  // A `globalSetup` module failed, no test ran.
+  SetupError(AnyError),
+  Error(AnyError),
+}
+
+impl From<AnyResult> for CliResult {
+  fn from(result: AnyResult) -> Self {
+    result.map_or_else(CliResult::Error, |_| CliResult::None)
+  }
+}
+
+impl CliResult {
+  pub(crate) fn exit_code(&self) -> i32 {
+    match self {
+      CliResult::None => exits::SUCCESS,
+      CliResult::Finished { summary, pass_with_no_tests } => {
+        if summary.file_errors > 0 {
+          exits::COLLECTION_ERROR
+        } else if summary.has_failures() {
+          exits::TESTS_FAILED
+        } else if summary.total() == 0
+          && !pass_with_no_tests
+          && !summary.empty_shard
+        {
+          exits::NO_TESTS_FOUND
+        } else {
+          exits::SUCCESS
+        }
+      }
+      CliResult::ConfigError(_) | CliResult::SetupError(_) => {
+        exits::COLLECTION_ERROR
+      }
+      CliResult::Error(_) => exits::INTERNAL_ERROR,
+    }
+  }
 }
 
-// TODO: expand impl
 impl std::process::Termination for CliResult {
-  fn report(self) -> std::process::ExitCode {
-    ExitCode::from(0)
+  fn report(self) -> ExitCode {
+    let exit_code = self.exit_code();
+
+    match &self {
+      CliResult::Finished { .. }
+        if exit_code == exits::NO_TESTS_FOUND =>
+      {
+        println!(
+          "{}",
+          Color::LightYellow.paint(
+            "No tests found, pass --passWithNoTests to exit with 0."
+          )
+        );
+      }
+      CliResult::ConfigError(error) => {
+        eprintln!("Failed to load the config file: {:?}", error);
+      }
+      CliResult::SetupError(error) => eprintln!("{:?}", error),
+      CliResult::Error(error) => eprintln!("Error: {:?}", error),
+      _ => {}
+    }
+
+    ExitCode::from(exit_code as u8)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use kurtex_core::runner::summary::RunSummary;
+
+  use crate::exits;
+  use crate::result::CliResult;
+
+  fn exit_code(summary: RunSummary, pass_with_no_tests: bool) -> i32 {
+    CliResult::Finished { summary, pass_with_no_tests }.exit_code()
+  }
+
+  #[test]
+  fn test_exit_code() {
+    let passed =
+      RunSummary { passed: 2, skipped: 1, ..Default::default() };
+    assert_eq!(exit_code(passed.clone(), false), exits::SUCCESS);
+
+    let failed = RunSummary { failed: 1, ..passed.clone() };
+    assert_eq!(exit_code(failed.clone(), true), exits::TESTS_FAILED);
+
+    let hook_failed = RunSummary { unhandled_errors: 1, ..passed };
+    assert_eq!(exit_code(hook_failed, false), exits::TESTS_FAILED);
+
+    // Files that failed to load take precedence over failed tests.
+    let file_error = RunSummary { file_errors: 1, ..failed };
+    assert_eq!(exit_code(file_error, false), exits::COLLECTION_ERROR);
+
+    let empty = RunSummary::default();
+    assert_eq!(exit_code(empty.clone(), false), exits::NO_TESTS_FOUND);
+    assert_eq!(exit_code(empty, true), exits::SUCCESS);
+
+    // Other shards ran the test files.
+    let empty_shard =
+      RunSummary { empty_shard: true, ..Default::default() };
+    assert_eq!(exit_code(empty_shard, false), exits::SUCCESS);
+
+    // Skipped and todo tests were found, they just did not run.
+    let skipped =
+      RunSummary { skipped: 1, todo: 1, ..Default::default() };
+    assert_eq!(exit_code(skipped, false), exits::SUCCESS);
+
+    assert_eq!(CliResult::None.exit_code(), exits::SUCCESS);
+    assert_eq!(
+      CliResult::ConfigError(anyhow::anyhow!("config")).exit_code(),
+      exits::COLLECTION_ERROR
+    );
+    assert_eq!(
+      CliResult::SetupError(anyhow::anyhow!("setup")).exit_code(),
+      exits::COLLECTION_ERROR
+    );
+    assert_eq!(
+      CliResult::Error(anyhow::anyhow!("error")).exit_code(),
+      exits::INTERNAL_ERROR
+    );
   }
 }
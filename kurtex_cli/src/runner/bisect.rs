@@ -0,0 +1,108 @@
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::bail;
+use clap::ArgMatches;
+use nu_ansi_term::Color;
+
+use kurtex_core::config::loader::ConfigLoader;
+use kurtex_core::runner::bisect::{bisect, BisectResult, TaskId};
+use kurtex_core::runner::collector::TestRunnerConfig;
+use kurtex_core::util::tokio::run_async;
+use kurtex_core::ShuffleOptions;
+
+use crate::result::CliResult;
+use crate::runner::{
+  create_tokio_runtime, emit_options, resolve_base_config,
+};
+
+pub fn run_bisect(mut opts: ArgMatches) -> CliResult {
+  let target_id = opts.remove_one::<String>("test").unwrap();
+  let shuffle = opts.get_flag("sequence.shuffle");
+  let seed = opts.remove_one::<u64>("sequence.seed");
+  let base_config = resolve_base_config(&mut opts);
+  let mut runner_config = TestRunnerConfig {
+    shuffle: ShuffleOptions {
+      files: shuffle,
+      suites: shuffle,
+      tasks: shuffle,
+    },
+    seed,
+    ..base_config
+  };
+
+  let bisect = Box::pin(async move {
+    let config_loader =
+      ConfigLoader::new(runner_config.config_path.display().to_string());
+    let (config_file, config_dependencies) =
+      config_loader.load_with_dependencies().await?;
+    let configured_seed =
+      config_file.sequence.as_ref().and_then(|sequence| sequence.seed);
+    let seeded = seed.or(configured_seed).is_some();
+
+    runner_config.adjust_config_file(config_file);
+    runner_config.config_dependencies = config_dependencies;
+
+    // A fresh random order would not reproduce the failing run.
+    if runner_config.shuffle.is_enabled() && !seeded {
+      bail!(
+        "Shuffle is enabled, pass the seed of the failing run with \
+         --sequence.seed=SEED"
+      );
+    }
+
+    let config = Rc::new(runner_config);
+    let root_dir = config.root_dir.clone();
+    let report =
+      bisect(config, emit_options(), &target_id, |probe, preceding| {
+        println!("Run {}: {} preceding tests", probe, preceding)
+      })
+      .await?;
+
+    println!("Finished after {} runs.", report.probes);
+
+    if let Some(seed) = report.seed {
+      println!("Seed {} (--sequence.seed={})", seed, seed);
+    }
+
+    println!();
+
+    match report.result {
+      BisectResult::FailsAlone => println!(
+        "{}",
+        Color::LightYellow.paint(
+          "The test fails on its own, it does not depend on order."
+        )
+      ),
+      BisectResult::NotReproduced => println!(
+        "{}",
+        Color::LightYellow
+          .paint("The test passes after all the preceding tests.")
+      ),
+      BisectResult::Culprit(culprit) => {
+        println!("{}", Color::Red.paint("Found the culprit:"));
+        println!("  {}", display_task(&culprit, &root_dir));
+      }
+      BisectResult::Culprits(culprits) => {
+        println!(
+          "{}",
+          Color::Red.paint("The failure needs these tests together:")
+        );
+
+        for culprit in &culprits {
+          println!("  {}", display_task(culprit, &root_dir));
+        }
+      }
+    }
+
+    Ok(())
+  });
+
+  run_async(bisect, Some(create_tokio_runtime())).into()
+}
+
+fn display_task(task: &TaskId, root_dir: &Path) -> String {
+  let file = task.file.strip_prefix(root_dir).unwrap_or(&task.file);
+
+  format!("{} > {}", file.display(), task.task_path)
+}
@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use clap::ArgMatches;
+use nu_ansi_term::{Color, Style};
+
+use kurtex_core::runner::list::{list_tests, ListedFile, ListedTask};
+use kurtex_core::util::tokio::run_async;
+use kurtex_core::CollectorMode;
+
+use crate::result::CliResult;
+use crate::runner::{
+  create_tokio_runtime, emit_options, load_config_file,
+  resolve_base_config,
+};
+
+pub fn run_list(mut opts: ArgMatches) -> CliResult {
+  let as_json = opts.get_flag("json");
+  let runner_config = resolve_base_config(&mut opts);
+
+  let list = Box::pin(async move {
+    let config = load_config_file(runner_config).await?;
+    let root_dir = config.root_dir.clone();
+    let listed_files = list_tests(config, emit_options()).await?;
+
+    if as_json {
+      println!("{}", serde_json::to_string_pretty(&listed_files)?);
+    } else {
+      print_listed_files(&listed_files, &root_dir);
+    }
+
+    Ok(())
+  });
+
+  run_async(list, Some(create_tokio_runtime())).into()
+}
+
+fn print_listed_files(listed_files: &[ListedFile], root_dir: &Path) {
+  let mut counts = [0usize; 5];
+
+  for listed_file in listed_files {
+    let file = listed_file.file.strip_prefix(root_dir);
+    let file = file.unwrap_or(&listed_file.file).display();
+    println!("{}", Style::new().bold().paint(file.to_string()));
+
+    if let Some(error) = &listed_file.error {
+      println!("  {}", Color::Red.paint(error));
+    }
+
+    for task in &listed_file.tests {
+      print_task(task, 1);
+    }
+
+    for suite in &listed_file.suites {
+      println!("  {} {}", suite.name, paint_mode(suite.mode));
+
+      for task in &suite.tests {
+        print_task(task, 2);
+      }
+    }
+
+    for task in listed_file.all_tests() {
+      let index = match task.mode {
+        CollectorMode::Run => 0,
+        CollectorMode::Skip => 1,
+        CollectorMode::Only => 2,
+        CollectorMode::Todo => 3,
+        CollectorMode::Fails => 4,
+      };
+
+      counts[index] += 1;
+    }
+  }
+
+  let [run, skip, only, todo, fails] = counts;
+  let total = run + skip + only + todo + fails;
+
+  println!();
+  println!(
+    "{} files, {} tests ({} run, {} skip, {} only, {} todo, {} fails)",
+    listed_files.len(),
+    total,
+    run,
+    skip,
+    only,
+    todo,
+    fails
+  );
+}
+
+fn print_task(task: &ListedTask, depth: usize) {
+  let indent = "  ".repeat(depth);
+  println!("{}{} {}", indent, task.name, paint_mode(task.mode));
+}
+
+fn paint_mode(mode: CollectorMode) -> String {
+  let (color, label) = match mode {
+    CollectorMode::Run => (Color::LightGreen, "run"),
+    CollectorMode::Skip => (Color::LightGray, "skip"),
+    CollectorMode::Only => (Color::LightYellow, "only"),
+    CollectorMode::Todo => (Color::Blue, "todo"),
+    CollectorMode::Fails => (Color::LightBlue, "fails"),
+  };
+
+  color.paint(format!("[{}]", label)).to_string()
+}
@@ -0,0 +1,42 @@
+use std::path::PathBuf;
+
+use clap::ArgMatches;
+
+use kurtex_core::reporter::KurtexDefaultReporter;
+use kurtex_core::runner::report::merge_reports;
+use kurtex_core::util::tokio::run_async;
+use kurtex_core::ReporterKind;
+
+use crate::result::CliResult;
+use crate::runner::{
+  create_tokio_runtime, load_config_file, resolve_base_config,
+};
+
+pub fn run_merge_reports(mut opts: ArgMatches) -> CliResult {
+  let reports_dir = opts.remove_one::<PathBuf>("dir").unwrap();
+  let runner_config = resolve_base_config(&mut opts);
+
+  let merge = Box::pin(async move {
+    let config = load_config_file(runner_config).await?;
+    let report = merge_reports(&config.root_dir.join(reports_dir))?;
+    let reporter = KurtexDefaultReporter::new();
+
+    // A merged blob written next to the shard blobs would be merged
+    // again by the next `merge-reports`.
+    let mut reporters = config.reporters();
+    reporters
+      .retain(|reporter_kind| *reporter_kind != ReporterKind::Blob);
+
+    if reporters.is_empty() {
+      reporters.push(ReporterKind::Default);
+    }
+
+    for reporter_kind in reporters {
+      reporter_kind.report(&reporter, &report, &config)?;
+    }
+
+    Ok(())
+  });
+
+  run_async(merge, Some(create_tokio_runtime())).into()
+}
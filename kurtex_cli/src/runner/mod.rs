@@ -3,17 +3,30 @@ use std::rc::Rc;
 
 use anyhow::Context;
 use clap::ArgMatches;
+use regex::Regex;
 use tokio::time;
 
 use kurtex_core::config::loader::ConfigLoader;
 use kurtex_core::runner::collector::TestRunnerConfig;
+use kurtex_core::runner::filter::LocationFilter;
+use kurtex_core::runner::global_setup::GlobalSetupError;
+use kurtex_core::runner::shard::Shard;
+use kurtex_core::util::git::ChangedFilesScope;
 use kurtex_core::util::tokio::run_async;
 use kurtex_core::walk::{Extensions, Walk};
-use kurtex_core::EmitRuntimeOptions;
+use kurtex_core::{
+  AnyResult, EmitRuntimeOptions, HookSequence, ReporterKind,
+  ShuffleOptions,
+};
 
 use crate::result::CliResult;
 use crate::settings;
 
+mod bisect;
+mod list;
+mod merge_reports;
+mod related;
+
 /// A trait for exposing functionality to the CLI.
 pub trait Runner {
   type Options;
@@ -42,64 +55,183 @@ impl Runner for CliRunner {
 
   fn run(mut self) -> CliResult {
     let mut opts = self.options;
-    let root_dir = opts.remove_one::<PathBuf>("root");
-    let watch = opts.remove_one::<bool>("watch").unwrap();
-    let globals = opts.remove_one::<bool>("globals").unwrap();
-    let parallel = opts.remove_one::<bool>("parallel").unwrap();
-
-    let config_path = opts.remove_one::<String>("config").unwrap();
-    let mut config_path = PathBuf::from(config_path);
-
-    let current_dir =
-      std::env::current_dir().context("Unable to get CWD.").unwrap();
-    let root_dir = root_dir.unwrap_or(current_dir);
-    root_dir.join(&config_path).clone_into(&mut config_path);
-
-    let rt = tokio::runtime::Builder::new_current_thread()
-      .enable_all()
-      .build()
-      .unwrap();
-
-    let config_path = if config_path.exists() {
-      config_path
-    } else {
-      let mut paths = find_kurtex_config(&root_dir);
-      let first_match = paths.drain(..1).next();
-
-      first_match.unwrap()
-    };
 
-    let config_path_ = config_path.clone();
-    let mut runner_config = TestRunnerConfig {
-      watch,
-      globals,
-      config_path,
-      root_dir,
-      parallel,
-      ..Default::default()
+    match opts.remove_subcommand() {
+      Some((command, sub_opts)) if command == "related" => {
+        related::run_related(sub_opts)
+      }
+      Some((command, sub_opts)) if command == "bisect" => {
+        bisect::run_bisect(sub_opts)
+      }
+      Some((command, sub_opts)) if command == "list" => {
+        list::run_list(sub_opts)
+      }
+      Some((command, sub_opts)) if command == "merge-reports" => {
+        merge_reports::run_merge_reports(sub_opts)
+      }
+      _ => run_tests(opts),
+    }
+  }
+}
+
+fn run_tests(mut opts: ArgMatches) -> CliResult {
+  let watch = opts.remove_one::<bool>("watch").unwrap();
+  let globals = opts.remove_one::<bool>("globals").unwrap();
+  let parallel = opts.remove_one::<bool>("parallel").unwrap();
+  let changed = opts
+    .remove_one::<String>("changed")
+    .map(|revision| ChangedFilesScope::from_revision(&revision));
+
+  let filters = opts
+    .remove_many::<String>("filters")
+    .map(|filters| filters.collect::<Vec<_>>())
+    .unwrap_or_default();
+  let location_filters = filters
+    .iter()
+    .filter_map(|filter| LocationFilter::parse(filter))
+    .collect::<Vec<_>>();
+  // Files of `file:line` filters are matched by the location.
+  let file_filters = filters
+    .into_iter()
+    .filter(|filter| LocationFilter::parse(filter).is_none())
+    .collect();
+  let test_name_pattern = opts.remove_one::<Regex>("test-name-pattern");
+  let shard = opts.remove_one::<Shard>("shard");
+  let durations_path = opts.remove_one::<PathBuf>("durations");
+  let shuffle = opts.get_flag("sequence.shuffle");
+  let seed = opts.remove_one::<u64>("sequence.seed");
+  let hook_sequence = opts.remove_one::<HookSequence>("sequence.hooks");
+  let retry = opts.remove_one::<u32>("retry");
+  let repeats = opts.remove_one::<u32>("repeat");
+  let bail = opts.remove_one::<u32>("bail");
+  let pass_with_no_tests = opts.get_flag("passWithNoTests");
+
+  let base_config = resolve_base_config(&mut opts);
+  let durations_path =
+    durations_path.map(|path| base_config.root_dir.join(path));
+
+  let runner_config = TestRunnerConfig {
+    watch,
+    globals,
+    parallel,
+    changed,
+    file_filters,
+    test_name_pattern,
+    location_filters,
+    shard,
+    durations_path,
+    shuffle: ShuffleOptions {
+      files: shuffle,
+      suites: shuffle,
+      tasks: shuffle,
+    },
+    seed,
+    hook_sequence,
+    retry,
+    repeats,
+    bail,
+    pass_with_no_tests,
+    ..base_config
+  };
+
+  let runner = Box::pin(launch_tests(runner_config));
+
+  run_async(runner, Some(create_tokio_runtime()))
+    .unwrap_or_else(CliResult::Error)
+}
+
+async fn launch_tests(
+  runner_config: TestRunnerConfig,
+) -> AnyResult<CliResult> {
+  let runner_config = match load_config_file(runner_config).await {
+    Ok(runner_config) => runner_config,
+    Err(error) => return Ok(CliResult::ConfigError(error)),
+  };
+  let pass_with_no_tests = runner_config.pass_with_no_tests;
+  let watch = runner_config.watch;
+  let summary =
+    match kurtex_core::runner::launch(runner_config, emit_options())
+      .await
+    {
+      Ok(summary) => summary,
+      Err(error) if error.is::<GlobalSetupError>() => {
+        return Ok(CliResult::SetupError(error));
+      }
+      Err(error) => return Err(error),
     };
 
-    let config_loader =
-      ConfigLoader::new(config_path_.display().to_string());
+  // Watch mode is left on purpose, the last rerun does not decide
+  // the exit code.
+  if watch {
+    return Ok(CliResult::None);
+  }
+
+  Ok(CliResult::Finished { summary, pass_with_no_tests })
+}
 
-    let runner = Box::pin(async move {
-      let config_file = config_loader.load().await.unwrap();
-      let runtime_snapshot = settings::RUNTIME_SNAPSHOT;
+// Resolves the root directory and config path shared by all commands.
+pub fn resolve_base_config(opts: &mut ArgMatches) -> TestRunnerConfig {
+  let root_dir = opts.remove_one::<String>("root").map(PathBuf::from);
+  let config_path = opts.remove_one::<String>("config").unwrap();
+  let mut config_path = PathBuf::from(config_path);
 
-      runner_config.adjust_config_file(config_file);
-      let emit_options =
-        Rc::new(EmitRuntimeOptions { runtime_snapshot });
+  let current_dir =
+    std::env::current_dir().context("Unable to get CWD.").unwrap();
+  let root_dir = root_dir.unwrap_or(current_dir);
+  root_dir.join(&config_path).clone_into(&mut config_path);
 
-      kurtex_core::runner::launch(Rc::new(runner_config), emit_options)
-        .await
-    });
+  let config_path = if config_path.exists() {
+    config_path
+  } else {
+    let mut paths = find_kurtex_config(&root_dir);
+    let first_match = paths.drain(..1).next();
 
-    run_async(runner, Some(rt));
+    first_match.unwrap()
+  };
 
-    CliResult::None
+  let reporters = opts
+    .remove_many::<ReporterKind>("reporter")
+    .map(|reporters| reporters.collect())
+    .unwrap_or_default();
+
+  TestRunnerConfig {
+    config_path,
+    root_dir,
+    reporters,
+    ..Default::default()
   }
 }
 
+pub async fn load_config_file(
+  mut runner_config: TestRunnerConfig,
+) -> AnyResult<Rc<TestRunnerConfig>> {
+  let config_loader =
+    ConfigLoader::new(runner_config.config_path.display().to_string());
+  let (config_file, config_dependencies) =
+    config_loader.load_with_dependencies().await?;
+
+  runner_config.adjust_config_file(config_file);
+  runner_config.config_dependencies = config_dependencies;
+
+  Ok(Rc::new(runner_config))
+}
+
+pub fn emit_options() -> Rc<EmitRuntimeOptions> {
+  let runtime_snapshot = settings::RUNTIME_SNAPSHOT;
+
+  Rc::new(EmitRuntimeOptions {
+    runtime_snapshot,
+    provided: Default::default(),
+  })
+}
+
+pub fn create_tokio_runtime() -> tokio::runtime::Runtime {
+  tokio::runtime::Builder::new_current_thread()
+    .enable_all()
+    .build()
+    .unwrap()
+}
+
 pub fn find_kurtex_config(root_dir: &PathBuf) -> Vec<PathBuf> {
   let mut paths = Walk::new(&VALID_CONFIG_FILES, &root_dir)
     .with_extensions(Extensions(VALID_EXTENSIONS.to_vec()))
@@ -113,3 +245,761 @@ pub fn find_kurtex_config(root_dir: &PathBuf) -> Vec<PathBuf> {
 
   paths
 }
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+
+  use kurtex_core::runner::collector::TestRunnerConfig;
+  use kurtex_core::runner::list::{list_tests, ListedFile};
+  use kurtex_core::runner::summary::RunSummary;
+  use kurtex_core::runner::{
+    reload_config, reload_runner, resolve_related_tests, start_session,
+  };
+  use kurtex_core::util::fs::kurtex_tmp_dir;
+  use kurtex_core::util::tokio::run_async;
+  use kurtex_core::watcher::resolver::WatcherResolver;
+
+  use crate::exits;
+  use crate::result::CliResult;
+  use crate::runner::{
+    create_tokio_runtime, emit_options, launch_tests, load_config_file,
+  };
+
+  const CONFIG: (&str, &str) =
+    ("kurtex.config.json", r#"{ "includes": ["*.test.ts"] }"#);
+
+  const PASSING_TEST: &str = r#"
+test('passes', () => {})
+"#;
+
+  const HOOKS_TEST: &str = r#"
+const events: string[] = []
+
+describe('hooks', () => {
+    beforeEach(() => {
+        events.push('before')
+
+        return () => {
+            events.push('cleanup')
+        }
+    })
+
+    afterEach(() => {
+        events.push('after')
+    })
+
+    test('passes', () => {
+        events.push('test')
+    })
+
+    test('fails', () => {
+        throw new Error('test error')
+    })
+})
+
+describe('failing cleanup', () => {
+    beforeEach(() => () => {
+        throw new Error('cleanup error')
+    })
+
+    test('fails in cleanup', () => {})
+})
+
+describe('around each', () => {
+    aroundEach(async runTest => {
+        events.push('around start')
+        await runTest()
+        events.push('around end')
+    })
+
+    test('wrapped', () => {
+        events.push('wrapped')
+    })
+})
+
+describe('around each without runTest', () => {
+    aroundEach(() => {})
+
+    test('never runs', () => {
+        events.push('never runs')
+    })
+})
+
+describe('order', () => {
+    test('hooks ran in order', () => {
+        const expected = [
+            'before', 'test', 'after', 'cleanup',
+            'before', 'after', 'cleanup',
+            'around start', 'wrapped', 'around end'
+        ]
+
+        if (events.join() !== expected.join()) {
+            throw new Error(`Unexpected order: ${events.join()}`)
+        }
+    })
+})
+"#;
+
+  const ORDER_TEST: &str = r#"
+const events: string[] = []
+
+test('first', () => {
+    events.push('first')
+})
+
+describe('outer', () => {
+    test('outer 1', () => {
+        events.push('outer 1')
+    })
+
+    describe('inner', () => {
+        test('inner', () => {
+            events.push('inner')
+        })
+    })
+
+    test('outer 2', () => {
+        events.push('outer 2')
+    })
+})
+
+test('last', () => {
+    events.push('last')
+})
+
+describe('order', () => {
+    test('declaration order', () => {
+        const expected = ['first', 'outer 1', 'inner', 'outer 2', 'last']
+
+        if (events.join() !== expected.join()) {
+            throw new Error(`Unexpected order: ${events.join()}`)
+        }
+    })
+})
+"#;
+
+  const SETUP_CONFIG: (&str, &str) = (
+    "kurtex.config.json",
+    r#"{ "includes": ["*.test.ts"], "setupFiles": ["setup.ts"] }"#,
+  );
+
+  const SETUP_FILE: &str = r#"
+const events: string[] = []
+;(globalThis as any).setupEvents = events
+
+beforeEach(() => {
+    events.push('setup before')
+})
+
+afterEach(() => {
+    events.push('setup after')
+})
+"#;
+
+  const NESTED_HOOKS_TEST: &str = r#"
+const events: string[] = (globalThis as any).setupEvents
+
+describe('outer', () => {
+    beforeEach(() => {
+        events.push('outer before')
+    })
+
+    afterEach(() => {
+        events.push('outer after')
+    })
+
+    describe('inner', () => {
+        beforeEach(() => {
+            events.push('inner before')
+        })
+
+        afterEach(() => {
+            events.push('inner after')
+        })
+
+        test('nested', () => {
+            events.push('nested')
+        })
+    })
+})
+
+describe('order', () => {
+    test('hooks wrap nested tests', () => {
+        const expected = [
+            'setup before', 'outer before', 'inner before',
+            'nested',
+            'inner after', 'outer after', 'setup after',
+            'setup before'
+        ]
+
+        if (events.join() !== expected.join()) {
+            throw new Error(`Unexpected order: ${events.join()}`)
+        }
+    })
+})
+"#;
+
+  const TASK_ARGUMENTS_TEST: &str = r#"
+const attempts: Record<string, number> = {}
+
+// Fails on the first attempt and passes on the retry.
+function flaky(name: string) {
+    return () => {
+        attempts[name] = (attempts[name] ?? 0) + 1
+
+        if (attempts[name] === 1) {
+            throw new Error('first attempt')
+        }
+    }
+}
+
+test('options first', { retry: 1 }, flaky('options first'))
+test('options last', flaky('options last'), { retry: 1 })
+test('numeric timeout', () => {}, 5000)
+test('array is not options', () => {}, [] as any)
+
+const extended = test.extend({ value: 1 })
+extended('extended options first', { retry: 1 }, flaky('extended'))
+extended('extended numeric timeout', () => {}, 5000)
+"#;
+
+  const GLOBAL_SETUP_CONFIG: (&str, &str) = (
+    "kurtex.config.json",
+    r#"{ "includes": ["*.test.ts"], "globalSetup": ["global.ts"] }"#,
+  );
+
+  const INJECT_TEST: &str = r#"
+test('inject', () => {
+    if (inject('port') !== 3000) {
+        throw new Error(`Unexpected port: ${inject('port')}`)
+    }
+})
+"#;
+
+  const EACH_TEST: &str = r#"
+test.each([
+    ['a', 2.5, 2.5, '1.25px', { x: 1 }, [1, 2]],
+    ['b', '3', -3.7, '2', null, 'c']
+])('%s %d %i %f %j %o %# %%', () => {})
+
+test.each([
+    { name: 'one', user: { age: 2 } }
+])('$name is $user.age, $missing', () => {})
+
+test.each`
+    a    | b    | sum
+    ${1} | ${2} | ${3}
+    ${2} | ${3} | ${5}
+`('$a + $b = $sum', ({ a, b, sum }) => {
+    if (a + b !== sum) {
+        throw new Error(`${a} + ${b} !== ${sum}`)
+    }
+})
+"#;
+
+  const EACH_TABLE_MISMATCH_TEST: &str = r#"
+test.each`
+    a    | b
+    ${1} | ${2}
+    ${3}
+`('$a and $b', () => {})
+"#;
+
+  const FIXTURES_TEST: &str = r#"
+const events: string[] = []
+
+const fixtureTest = test.extend<{ config: string; db: string; repo: string }>({
+    config: async ({}, use) => {
+        events.push('config setup')
+        await use('config')
+        events.push('config teardown')
+    },
+    db: async ({ config }, use) => {
+        events.push('db setup')
+        await use(`db(${config})`)
+        events.push('db teardown')
+    },
+    repo: async ({ db }, use) => {
+        events.push('repo setup')
+        await use(`repo(${db})`)
+        events.push('repo teardown')
+    }
+})
+
+fixtureTest('dependent fixtures', ({ repo }) => {
+    if (repo !== 'repo(db(config))') {
+        throw new Error(`Unexpected repo: ${repo}`)
+    }
+
+    events.push('test')
+})
+
+test('teardown order', () => {
+    const expected = [
+        'config setup', 'db setup', 'repo setup',
+        'test',
+        'repo teardown', 'db teardown', 'config teardown'
+    ]
+
+    if (events.join() !== expected.join()) {
+        throw new Error(`Unexpected order: ${events.join()}`)
+    }
+})
+
+const cyclicTest = test.extend<{ a: unknown; b: unknown }>({
+    a: async ({ b }, use) => {
+        await use(b)
+    },
+    b: async ({ a }, use) => {
+        await use(a)
+    }
+})
+
+cyclicTest('cycle', ({ a }) => {})
+"#;
+
+  const MOCKS_TEST: &str = r#"
+function assert(condition: unknown, message: string) {
+    if (!condition) {
+        throw new Error(message)
+    }
+}
+
+test('matchers', () => {
+    const mock = kurtex.fn((value: unknown) => value)
+    kurtex.expect(mock).not.toHaveBeenCalled()
+
+    mock(new Map([['a', { b: 1 }]]))
+    mock(new Set([1, { c: 2 }]))
+    mock(new Uint8Array([1, 2, 3]))
+
+    kurtex.expect(mock).toHaveBeenCalled()
+    kurtex.expect(mock).toHaveBeenCalledTimes(3)
+    kurtex.expect(mock).toHaveBeenCalledWith(new Map([['a', { b: 1 }]]))
+    kurtex.expect(mock).not.toHaveBeenCalledWith(new Map([['a', { b: 2 }]]))
+    kurtex.expect(mock).toHaveBeenCalledWith(new Set([{ c: 2 }, 1]))
+    kurtex.expect(mock).not.toHaveBeenCalledWith(new Set([1, 2]))
+    kurtex.expect(mock).toHaveBeenLastCalledWith(new Uint8Array([1, 2, 3]))
+    kurtex.expect(mock).not.toHaveBeenLastCalledWith(new Uint8Array([1, 2]))
+    kurtex.expect(mock).not.toHaveBeenCalledWith(new Uint16Array([1, 2, 3]))
+    kurtex.expect(mock).toHaveReturnedWith(new Set([1, { c: 2 }]))
+})
+
+test('implementations and spies', () => {
+    const mock = kurtex.fn(() => 'default').mockReturnValueOnce('once')
+    assert(mock() === 'once', 'once implementation')
+    assert(mock() === 'default', 'default implementation')
+
+    const object = { add: (a: number, b: number) => a + b }
+    const spy = kurtex.spyOn(object, 'add')
+
+    assert(object.add(1, 2) === 3, 'spy calls the original')
+    kurtex.expect(spy).toHaveBeenCalledWith(1, 2)
+    kurtex.expect(spy).toHaveReturnedWith(3)
+    assert(kurtex.spyOn(object, 'add') === spy, 'spies are reused')
+
+    spy.mockRestore()
+    assert(!kurtex.isMockFunction(object.add), 'spy is restored')
+})
+
+test.fails('failing matcher', () => {
+    kurtex.expect(kurtex.fn()).toHaveBeenCalled()
+})
+
+test.fails('not a mock', () => {
+    kurtex.expect(() => {}).toHaveBeenCalled()
+})
+"#;
+
+  // Has no tests, so its mocks are only reset by other files when
+  // the scoping is broken.
+  const MOCK_OWNER_TEST: &str = r#"
+const object = { greet: () => 'hi' }
+kurtex.spyOn(object, 'greet')
+
+;(globalThis as any).owner = { mock: kurtex.fn(() => 'value'), object }
+"#;
+
+  // `MODE` is replaced with the config option under test.
+  const MOCK_SCOPE_TEST: &str = r#"
+const mode: string = 'MODE'
+const owner = () => (globalThis as any).owner
+const object = { greet: () => 'hi' }
+const mock = kurtex.fn(() => 'value')
+
+function assert(condition: unknown, message: string) {
+    if (!condition) {
+        throw new Error(`${mode}: ${message}`)
+    }
+}
+
+test('uses mocks', () => {
+    mock.mockReturnValue('changed')
+    mock()
+    kurtex.spyOn(object, 'greet').mockReturnValue('spied')
+    owner().mock.mockReturnValue('changed')
+    owner().mock()
+    owner().object.greet()
+})
+
+test('resets mocks of this file only', () => {
+    kurtex.expect(mock).not.toHaveBeenCalled()
+
+    const [returned, greeting, spied] = {
+        clearMocks: ['changed', 'spied', true],
+        resetMocks: ['value', 'hi', true],
+        restoreMocks: ['value', 'hi', false]
+    }[mode]!
+
+    assert(mock() === returned, 'mock implementation')
+    assert(object.greet() === greeting, 'spy implementation')
+    assert(kurtex.isMockFunction(object.greet) === spied, 'spy restore')
+
+    kurtex.expect(owner().mock).toHaveBeenCalledTimes(1)
+    kurtex.expect(owner().object.greet).toHaveBeenCalledTimes(1)
+    assert(owner().mock() === 'changed', 'mock of another file')
+})
+"#;
+
+  const RETRY_TEST: &str = r#"
+let attempts = 0
+
+test('passes on the last retry', { retry: 2 }, () => {
+    attempts++
+
+    if (attempts < 3) {
+        throw new Error(`attempt ${attempts}`)
+    }
+})
+
+test.fails('expected failure', () => {
+    throw new Error('expected')
+})
+"#;
+
+  const UNEXPECTED_PASS_TEST: &str = r#"
+test.fails('unexpected pass', () => {})
+"#;
+
+  // Writes `files` into a fresh project directory, along with the
+  // config file at `files[0]`.
+  fn create_project(
+    name: &str,
+    files: &[(&str, &str)],
+  ) -> TestRunnerConfig {
+    let root_dir = kurtex_tmp_dir().join("tests").join(name);
+    let _ = fs::remove_dir_all(&root_dir);
+
+    for (path, source) in files {
+      let path = root_dir.join(path);
+      fs::create_dir_all(path.parent().unwrap()).unwrap();
+      fs::write(path, source).unwrap();
+    }
+
+    let root_dir = root_dir.canonicalize().unwrap();
+
+    TestRunnerConfig {
+      config_path: root_dir.join(files[0].0),
+      root_dir,
+      ..Default::default()
+    }
+  }
+
+  fn list_project(
+    name: &str,
+    files: &[(&str, &str)],
+  ) -> Vec<ListedFile> {
+    let runner_config = create_project(name, files);
+
+    let list = Box::pin(async move {
+      let config = load_config_file(runner_config).await?;
+
+      list_tests(config, emit_options()).await
+    });
+
+    run_async(list, Some(create_tokio_runtime())).unwrap()
+  }
+
+  fn run_project(name: &str, files: &[(&str, &str)]) -> RunSummary {
+    let runner_config = create_project(name, files);
+
+    let run = Box::pin(async move {
+      let config = load_config_file(runner_config).await?;
+
+      kurtex_core::runner::launch(config, emit_options()).await
+    });
+
+    run_async(run, Some(create_tokio_runtime())).unwrap()
+  }
+
+  #[test]
+  fn test_hooks_cleanups_and_around_each() {
+    let summary =
+      run_project("hooks", &[CONFIG, ("hooks.test.ts", HOOKS_TEST)]);
+
+    // `fails`, the failing cleanup and the aroundEach that never
+    // calls `runTest` fail, everything else passes in order.
+    assert_eq!(summary.passed, 3);
+    assert_eq!(summary.failed, 3);
+    assert_eq!(summary.unhandled_errors, 0);
+  }
+
+  #[test]
+  fn test_declaration_order() {
+    let summary =
+      run_project("order", &[CONFIG, ("order.test.ts", ORDER_TEST)]);
+
+    assert_eq!(summary.passed, 6);
+    assert_eq!(summary.failed, 0);
+  }
+
+  #[test]
+  fn test_nested_and_setup_file_hooks() {
+    let summary = run_project(
+      "nested-hooks",
+      &[
+        SETUP_CONFIG,
+        ("setup.ts", SETUP_FILE),
+        ("nested.test.ts", NESTED_HOOKS_TEST),
+      ],
+    );
+
+    assert_eq!(summary.passed, 2);
+    assert_eq!(summary.failed, 0);
+  }
+
+  #[test]
+  fn test_task_arguments() {
+    let summary = run_project(
+      "task-arguments",
+      &[CONFIG, ("arguments.test.ts", TASK_ARGUMENTS_TEST)],
+    );
+
+    // Options are forwarded in both orders, numbers and arrays are
+    // dropped instead of failing to deserialize.
+    assert_eq!(summary.passed, 6);
+    assert_eq!(summary.failed, 0);
+    assert_eq!(summary.file_errors, 0);
+  }
+
+  #[test]
+  fn test_global_setup_provide_and_inject() {
+    let summary = run_project(
+      "global-setup",
+      &[
+        GLOBAL_SETUP_CONFIG,
+        (
+          "global.ts",
+          "export function setup({ provide }) { provide('port', 3000) }",
+        ),
+        ("inject.test.ts", INJECT_TEST),
+      ],
+    );
+
+    assert_eq!(summary.passed, 1);
+    assert_eq!(summary.failed, 0);
+  }
+
+  #[test]
+  fn test_failing_global_setup() {
+    let runner_config = create_project(
+      "failing-global-setup",
+      &[
+        GLOBAL_SETUP_CONFIG,
+        ("global.ts", "throw new Error('setup error')"),
+        ("sum.test.ts", PASSING_TEST),
+      ],
+    );
+
+    let result = run_async(
+      Box::pin(launch_tests(runner_config)),
+      Some(create_tokio_runtime()),
+    )
+    .unwrap();
+
+    assert!(matches!(result, CliResult::SetupError(_)));
+    assert_eq!(result.exit_code(), exits::COLLECTION_ERROR);
+  }
+
+  #[test]
+  fn test_each_titles() {
+    let listed_files =
+      list_project("each", &[CONFIG, ("each.test.ts", EACH_TEST)]);
+    let titles = listed_files
+      .iter()
+      .flat_map(|file| file.all_tests())
+      .map(|task| task.name.as_str())
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      titles,
+      [
+        r#"a 2.5 2 1.25 {"x":1} [1,2] 0 %"#,
+        "b 3 -3 2 null c 1 %",
+        "one is 2, $missing",
+        "1 + 2 = 3",
+        "2 + 3 = 5",
+      ]
+    );
+
+    let summary =
+      run_project("each", &[CONFIG, ("each.test.ts", EACH_TEST)]);
+    assert_eq!(summary.passed, 5);
+    assert_eq!(summary.failed, 0);
+  }
+
+  #[test]
+  fn test_each_table_mismatch() {
+    let listed_files = list_project(
+      "each-mismatch",
+      &[CONFIG, ("each.test.ts", EACH_TABLE_MISMATCH_TEST)],
+    );
+    let error = listed_files[0].error.as_deref().unwrap_or_default();
+
+    assert!(error.contains("Table has 3 values for 2 columns."));
+  }
+
+  #[test]
+  fn test_fixtures() {
+    let summary = run_project(
+      "fixtures",
+      &[CONFIG, ("fixtures.test.ts", FIXTURES_TEST)],
+    );
+
+    // The cycle fails its test instead of hanging the run.
+    assert_eq!(summary.passed, 2);
+    assert_eq!(summary.failed, 1);
+    assert_eq!(summary.unhandled_errors, 0);
+  }
+
+  #[test]
+  fn test_mocks() {
+    let summary =
+      run_project("mocks", &[CONFIG, ("mocks.test.ts", MOCKS_TEST)]);
+
+    assert_eq!(summary.passed, 4);
+    assert_eq!(summary.failed, 0);
+  }
+
+  #[test]
+  fn test_mock_reset_scoping() {
+    for mode in ["clearMocks", "resetMocks", "restoreMocks"] {
+      let config =
+        format!(r#"{{ "includes": ["*.test.ts"], "{mode}": true }}"#);
+      let scope_test = MOCK_SCOPE_TEST.replace("MODE", mode);
+
+      let summary = run_project(
+        &format!("mock-scoping-{mode}"),
+        &[
+          ("kurtex.config.json", config.as_str()),
+          ("owner.test.ts", MOCK_OWNER_TEST),
+          ("scope.test.ts", scope_test.as_str()),
+        ],
+      );
+
+      assert_eq!(summary.passed, 2, "{mode}");
+      assert_eq!(summary.failed, 0, "{mode}");
+      assert_eq!(summary.file_errors, 0, "{mode}");
+    }
+  }
+
+  #[test]
+  fn test_retried_and_fails_tests_pass() {
+    let launch_project = |name: &str, files: &[(&str, &str)]| {
+      let runner_config = create_project(name, files);
+
+      run_async(
+        Box::pin(launch_tests(runner_config)),
+        Some(create_tokio_runtime()),
+      )
+      .unwrap()
+    };
+
+    let result =
+      launch_project("retry", &[CONFIG, ("retry.test.ts", RETRY_TEST)]);
+    match &result {
+      CliResult::Finished { summary, .. } => {
+        assert_eq!(summary.passed, 2);
+        assert_eq!(summary.failed, 0);
+      }
+      _ => panic!("expected a finished run, got {:?}", result),
+    }
+    assert_eq!(result.exit_code(), exits::SUCCESS);
+
+    let result = launch_project(
+      "unexpected-pass",
+      &[
+        CONFIG,
+        ("retry.test.ts", RETRY_TEST),
+        ("pass.test.ts", UNEXPECTED_PASS_TEST),
+      ],
+    );
+    match &result {
+      CliResult::Finished { summary, .. } => {
+        assert_eq!(summary.passed, 2);
+        assert_eq!(summary.failed, 1);
+      }
+      _ => panic!("expected a finished run, got {:?}", result),
+    }
+    assert_eq!(result.exit_code(), exits::TESTS_FAILED);
+  }
+
+  #[test]
+  fn test_setup_file_imports_rerun_all_tests() {
+    let runner_config = create_project(
+      "setup-graph",
+      &[
+        SETUP_CONFIG,
+        ("setup.ts", "import { helper } from './helper.ts'\nhelper()"),
+        ("helper.ts", "export function helper() {}"),
+        ("a.test.ts", PASSING_TEST),
+        ("b.test.ts", PASSING_TEST),
+      ],
+    );
+    let helper_path = runner_config.root_dir.join("helper.ts");
+
+    // Watch mode resolves changes with the graph of the runtime that
+    // ran the tests.
+    let resolve = Box::pin(async move {
+      let config = load_config_file(runner_config).await?;
+      let session = start_session(config, emit_options()).await?;
+      let mut resolver =
+        WatcherResolver::new(session.module_graph.clone());
+
+      Ok(resolve_related_tests(
+        &mut resolver,
+        &session.config,
+        helper_path,
+      ))
+    });
+
+    let related_tests =
+      run_async(resolve, Some(create_tokio_runtime())).unwrap();
+    assert_eq!(related_tests.map(|tests| tests.len()), Some(2));
+  }
+
+  #[test]
+  fn test_reload_config_twice() {
+    let runner_config =
+      create_project("reload", &[CONFIG, ("sum.test.ts", PASSING_TEST)]);
+
+    // Each reload replaces the runtime of the previous session.
+    let reload = Box::pin(async move {
+      let config = load_config_file(runner_config).await?;
+      let mut session = start_session(config, emit_options()).await?;
+
+      for _ in 0..2 {
+        let config = reload_config(&session.config).await?;
+        session = reload_runner(session, config, emit_options()).await?;
+      }
+
+      let passed = session.results.borrow().summary().passed;
+      Ok(passed)
+    });
+
+    let passed =
+      run_async(reload, Some(create_tokio_runtime())).unwrap();
+    assert_eq!(passed, 1);
+  }
+}
@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use clap::ArgMatches;
+use nu_ansi_term::{Color, Style};
+
+use kurtex_core::runner::related::{find_related_tests, RelatedReport};
+use kurtex_core::util::tokio::run_async;
+
+use crate::result::CliResult;
+use crate::runner::{
+  create_tokio_runtime, emit_options, load_config_file,
+  resolve_base_config,
+};
+
+pub fn run_related(mut opts: ArgMatches) -> CliResult {
+  let sources = opts
+    .remove_many::<PathBuf>("paths")
+    .map(|paths| paths.collect::<Vec<_>>())
+    .unwrap_or_default();
+  let with_import_chains = opts.get_flag("why");
+  let as_json = opts.get_flag("json");
+  let runner_config = resolve_base_config(&mut opts);
+
+  let related = Box::pin(async move {
+    let config = load_config_file(runner_config).await?;
+    let root_dir = config.root_dir.clone();
+    let related_report = find_related_tests(
+      config,
+      emit_options(),
+      sources,
+      with_import_chains,
+    )
+    .await?;
+
+    if as_json {
+      println!("{}", serde_json::to_string_pretty(&related_report)?);
+    } else {
+      print_related_tests(&related_report, &root_dir);
+    }
+
+    Ok(())
+  });
+
+  run_async(related, Some(create_tokio_runtime())).into()
+}
+
+fn print_related_tests(related_report: &RelatedReport, root_dir: &Path) {
+  let relative = |path: &Path| {
+    path.strip_prefix(root_dir).unwrap_or(path).display().to_string()
+  };
+
+  for related in &related_report.related {
+    println!("{}", Style::new().bold().paint(relative(&related.source)));
+
+    if related.tests.is_empty() {
+      println!("  {}", Color::LightGray.paint("no related tests"));
+    }
+
+    for test in &related.tests {
+      println!("  {}", relative(&test.file));
+
+      if let Some(import_chain) = &test.import_chain {
+        let import_chain = import_chain
+          .iter()
+          .map(|path| relative(path))
+          .collect::<Vec<_>>()
+          .join(" -> ");
+
+        println!("    {}", Color::LightGray.paint(import_chain));
+      }
+    }
+  }
+
+  if !related_report.failed_files.is_empty() {
+    let message = format!(
+      "\nFailed to load {} test files, they may be related too:",
+      related_report.failed_files.len()
+    );
+    eprintln!("{}", Color::LightYellow.paint(message));
+
+    for file in &related_report.failed_files {
+      eprintln!("  {}", relative(file));
+    }
+  }
+}
@@ -13,12 +13,13 @@ pub struct CollectorMetadata {
 }
 
 impl CollectorContext {
+  // The current node stays, tasks registered after a suite belong
+  // to its parent. The suite becomes current when its factory runs.
   pub fn register_collector(
     &mut self,
     new_node: RcCell<NodeCollectorManager>,
   ) {
-    self.collectors.push(new_node.clone());
-    self.set_current(new_node);
+    self.collectors.push(new_node);
   }
 
   pub fn set_current(&mut self, new_node: RcCell<NodeCollectorManager>) {
@@ -32,6 +33,13 @@ impl CollectorContext {
   pub fn acquire_collectors(&self) -> Vec<RcCell<NodeCollectorManager>> {
     self.collectors.clone()
   }
+
+  pub fn collector_at(
+    &self,
+    index: usize,
+  ) -> Option<RcCell<NodeCollectorManager>> {
+    self.collectors.get(index).cloned()
+  }
 }
 
 impl Default for CollectorContext {
@@ -9,6 +9,7 @@ pub mod structures;
 
 pub struct NodeCollectorManager {
   task_queue: Vec<Arc<Mutex<CollectorTask>>>,
+  children: Vec<CollectorChild>,
   inner_node: Arc<Mutex<CollectorNode>>,
   has_collected: bool,
   node_factory: Option<TestCallback>,
@@ -32,12 +33,14 @@ impl NodeCollectorManager {
     let collector_node = arc_mut!(CollectorNode {
       identifier,
       mode,
+      declared_mode: mode,
       ..CollectorNode::default()
     });
 
     NodeCollectorManager {
       inner_node: collector_node,
       task_queue,
+      children: Vec::new(),
       has_collected: false,
       on_file_level: false,
       node_factory,
@@ -70,6 +73,7 @@ impl NodeCollectorManager {
         {
           let mut inner_node = self.inner_node.lock().unwrap();
           inner_node.tasks = tasks_queue;
+          inner_node.children = self.children.clone();
         }
 
         self.inner_node.clone()
@@ -84,18 +88,62 @@ impl NodeCollectorManager {
       })
   }
 
+  // Nests the node in the suite registered by `parent`, it inherits
+  // the `skip`, `todo` and `only` modes of the parent.
+  pub fn with_parent(self, parent: &NodeCollectorManager) -> Self {
+    let (parent_path, parent_mode) = {
+      let parent_node = parent.inner_node.lock().unwrap();
+      (parent_node.suite_path.clone(), parent_node.mode)
+    };
+
+    {
+      let mut inner_node = self.inner_node.lock().unwrap();
+
+      if let CollectorIdentifier::Custom(name) = &inner_node.identifier {
+        let name = name.clone();
+        inner_node.suite_path = parent_path;
+        inner_node.suite_path.push(name);
+      }
+
+      inner_node.mode = match (parent_mode, inner_node.mode) {
+        (CollectorMode::Skip | CollectorMode::Todo, _) => parent_mode,
+        (CollectorMode::Only, CollectorMode::Run) => CollectorMode::Only,
+        (_, mode) => mode,
+      };
+    }
+
+    self
+  }
+
+  pub fn with_location(
+    self,
+    location: Option<CollectorLocation>,
+  ) -> Self {
+    self.inner_node.lock().unwrap().location = location;
+    self
+  }
+
   pub fn register_task(
     &mut self,
     name: String,
     callback: TestCallback,
     mode: CollectorMode,
+    location: Option<CollectorLocation>,
+    options: TaskOptions,
   ) {
-    let created_task =
-      Arc::new(Mutex::new(CollectorTask::new(name, callback, mode)));
+    let created_task = Arc::new(Mutex::new(CollectorTask::new(
+      name, callback, mode, location, options,
+    )));
 
+    self.children.push(CollectorChild::Task(created_task.clone()));
     self.task_queue.push(created_task);
   }
 
+  // Keeps the place of a nested suite among the tasks of this one.
+  pub fn register_child(&mut self, child: &NodeCollectorManager) {
+    self.children.push(CollectorChild::Node(child.inner_node.clone()));
+  }
+
   pub fn register_lifetime_hook(
     &mut self,
     hook_key: LifetimeHook,
@@ -1,4 +1,4 @@
-use std::fmt::Formatter;
+use std::fmt::{Display, Formatter};
 use std::ops::Deref;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -7,16 +7,29 @@ use std::sync::{Arc, Mutex};
 use anyhow::anyhow;
 use deno_core::v8;
 use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
 
+use crate::config::loader::HookSequence;
 use crate::error::AnyError;
 
-#[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(
+  Default, Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum CollectorMode {
   #[default]
   Run,
   Skip,
   Only,
   Todo,
+  // Expected failure, passes only when the callback throws.
+  Fails,
+}
+
+impl CollectorMode {
+  pub fn is_runnable(&self) -> bool {
+    matches!(self, CollectorMode::Run | CollectorMode::Fails)
+  }
 }
 
 impl<'a> deno_core::FromV8<'a> for CollectorMode {
@@ -42,11 +55,34 @@ impl FromStr for CollectorMode {
       "skip" => Ok(CollectorMode::Skip),
       "only" => Ok(CollectorMode::Only),
       "todo" => Ok(CollectorMode::Todo),
+      "fails" => Ok(CollectorMode::Fails),
       _ => Err(anyhow!("Invalid CollectorRunMode variant: '{}'", s)),
     }
   }
 }
 
+// Where a task or node was registered in its test file.
+#[derive(
+  Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize,
+)]
+pub struct CollectorLocation {
+  pub file: PathBuf,
+  pub line: u32,
+  pub column: u32,
+}
+
+// Options passed as `test(name, { retry: 2 }, fn)`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskOptions {
+  #[serde(default)]
+  pub retry: Option<u32>,
+
+  // Runs the test this many times to detect flakiness.
+  #[serde(default)]
+  pub repeats: Option<u32>,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub enum CollectorStatus {
   Custom(CollectorMode),
@@ -120,12 +156,44 @@ impl std::fmt::Debug for CollectorFile {
 pub struct CollectorNode {
   pub(crate) identifier: CollectorIdentifier,
   pub(crate) mode: CollectorMode,
+  // Mode as written in the test file, before `only` is resolved.
+  pub(crate) declared_mode: CollectorMode,
+  pub(crate) location: Option<CollectorLocation>,
+  // Names of the enclosing suites and this one, outermost first.
+  pub(crate) suite_path: Vec<String>,
   pub(crate) tasks: Vec<Arc<Mutex<CollectorTask>>>,
+  // Tasks and nested suites in declaration order.
+  pub(crate) children: Vec<CollectorChild>,
   pub(crate) status: CollectorStatus,
   pub(crate) error: Option<AnyError>,
   pub(crate) hook_manager: LifetimeHookManager,
 }
 
+#[derive(Clone)]
+pub enum CollectorChild {
+  Task(Arc<Mutex<CollectorTask>>),
+  Node(Arc<Mutex<CollectorNode>>),
+}
+
+impl CollectorNode {
+  // Suite path of a task registered on this node, e.g. `math > sum`.
+  pub fn task_path(&self, task: &CollectorTask) -> String {
+    self.path_of(&task.name)
+  }
+
+  fn path_of(&self, task_name: &str) -> String {
+    match self.suite_name() {
+      Some(suite_name) => format!("{} > {}", suite_name, task_name),
+      None => task_name.to_string(),
+    }
+  }
+
+  // Full suite name, e.g. `math > sum`, `None` on the file level.
+  pub fn suite_name(&self) -> Option<String> {
+    (!self.suite_path.is_empty()).then(|| self.suite_path.join(" > "))
+  }
+}
+
 impl std::fmt::Debug for CollectorNode {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     f.debug_struct("CollectorNode")
@@ -181,9 +249,19 @@ impl<'a> deno_core::FromV8<'a> for TestCallback {
 pub struct CollectorTask {
   pub(crate) name: String,
   pub(crate) mode: CollectorMode,
+  pub(crate) declared_mode: CollectorMode,
+  pub(crate) location: Option<CollectorLocation>,
   pub(crate) error: Option<AnyError>,
   pub(crate) status: CollectorStatus,
   pub(crate) callback: TestCallback,
+  pub(crate) options: TaskOptions,
+  // Runs it took to reach the final status, retries included.
+  pub(crate) attempts: u32,
+  // Repeated runs and how many of them passed.
+  pub(crate) runs: u32,
+  pub(crate) passes: u32,
+  // Why a runnable task was skipped, e.g. "bail".
+  pub(crate) skip_reason: Option<String>,
 }
 
 impl std::fmt::Debug for CollectorTask {
@@ -200,13 +278,22 @@ impl CollectorTask {
     name: String,
     callback: TestCallback,
     mode: CollectorMode,
+    location: Option<CollectorLocation>,
+    options: TaskOptions,
   ) -> Self {
     CollectorTask {
       name,
       mode,
+      declared_mode: mode,
+      location,
       error: None,
       status: CollectorStatus::Custom(mode),
       callback,
+      options,
+      attempts: 0,
+      runs: 0,
+      passes: 0,
+      skip_reason: None,
     }
   }
 }
@@ -249,6 +336,32 @@ impl LifetimeHookManager {
       .get(&hook_key)
       .unwrap_or_else(|| panic!("Wrong lifetime hook partition."))
   }
+
+  // Hooks in the order they run for the given sequence.
+  pub fn get_ordered(
+    &self,
+    hook_key: LifetimeHook,
+    sequence: HookSequence,
+  ) -> Vec<&TestCallback> {
+    let is_after_hook = matches!(
+      hook_key,
+      LifetimeHook::AfterAll | LifetimeHook::AfterEach
+    );
+
+    order_hooks(self.get_by(hook_key).iter(), is_after_hook, sequence)
+  }
+}
+
+// The stack sequence reverses after hooks, like nested cleanups.
+fn order_hooks<T>(
+  hooks: impl DoubleEndedIterator<Item = T>,
+  is_after_hook: bool,
+  sequence: HookSequence,
+) -> Vec<T> {
+  match sequence {
+    HookSequence::Stack if is_after_hook => hooks.rev().collect(),
+    _ => hooks.collect(),
+  }
 }
 
 impl Default for LifetimeHookManager {
@@ -279,6 +392,19 @@ impl<'a> deno_core::FromV8<'a> for LifetimeHook {
   }
 }
 
+impl Display for LifetimeHook {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let hook_key = match self {
+      LifetimeHook::BeforeAll => "beforeAll",
+      LifetimeHook::AfterAll => "afterAll",
+      LifetimeHook::BeforeEach => "beforeEach",
+      LifetimeHook::AfterEach => "afterEach",
+    };
+
+    f.write_str(hook_key)
+  }
+}
+
 impl FromStr for LifetimeHook {
   type Err = AnyError;
 
@@ -294,3 +420,36 @@ impl FromStr for LifetimeHook {
     Ok(lifetime_hook)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use crate::collector::structures::order_hooks;
+  use crate::{CollectorNode, HookSequence};
+
+  #[test]
+  fn test_task_path() {
+    let file_node = CollectorNode::default();
+    assert_eq!(file_node.path_of("sum"), "sum");
+    assert_eq!(file_node.suite_name(), None);
+
+    let nested_node = CollectorNode {
+      suite_path: vec!["math".to_string(), "int".to_string()],
+      ..Default::default()
+    };
+    assert_eq!(nested_node.path_of("sum"), "math > int > sum");
+    assert_eq!(nested_node.suite_name().as_deref(), Some("math > int"));
+  }
+
+  #[test]
+  fn test_order_hooks() {
+    let order = |is_after_hook, sequence| {
+      order_hooks([1, 2, 3].into_iter(), is_after_hook, sequence)
+    };
+
+    assert_eq!(order(false, HookSequence::List), [1, 2, 3]);
+    assert_eq!(order(true, HookSequence::List), [1, 2, 3]);
+    assert_eq!(order(false, HookSequence::Stack), [1, 2, 3]);
+    assert_eq!(order(true, HookSequence::Stack), [3, 2, 1]);
+    assert_eq!(order(true, HookSequence::Parallel), [1, 2, 3]);
+  }
+}
@@ -31,36 +31,284 @@ pub struct KurtexConfig {
   #[serde(default)]
   pub excludes: Vec<String>,
 
-  //  TODO:
-  // https://docs.rs/either/1.10.0/either/serde_untagged/index.html
   #[serde(default)]
-  pub watch: Option<bool>,
+  pub watch: Option<WatchConfig>,
 
   #[serde(default)]
   pub parallel: Option<bool>,
+
+  // Files outside the module graph that rerun tests when changed.
+  #[serde(default)]
+  pub force_rerun_triggers: Vec<RerunTrigger>,
+
+  #[serde(default)]
+  pub reporters: Vec<ReporterKind>,
+
+  #[serde(default)]
+  pub sequence: Option<SequenceConfig>,
+
+  // Reruns failed tests up to this many times.
+  #[serde(default)]
+  pub retry: Option<u32>,
+
+  // Runs every test this many times.
+  #[serde(default)]
+  pub repeats: Option<u32>,
+
+  // Stops the run after this many failed tests.
+  #[serde(default)]
+  pub bail: Option<u32>,
+
+  #[serde(default)]
+  pub pass_with_no_tests: Option<bool>,
+
+  // Modules run once before all test files, relative to the root.
+  #[serde(default)]
+  pub global_setup: Vec<String>,
+
+  // Modules evaluated before every test file, relative to the root.
+  #[serde(default)]
+  pub setup_files: Vec<String>,
+
+  // Applied to every mock before each test.
+  #[serde(default)]
+  pub clear_mocks: Option<bool>,
+
+  #[serde(default)]
+  pub reset_mocks: Option<bool>,
+
+  #[serde(default)]
+  pub restore_mocks: Option<bool>,
 }
 
-impl Default for KurtexConfig {
+// Either `watch: true` or `watch: { exclude: [...] }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WatchConfig {
+  Enabled(bool),
+  Options(WatchOptions),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct WatchOptions {
+  // Falls back to the `--watch` flag when omitted.
+  #[serde(default)]
+  pub enabled: Option<bool>,
+
+  #[serde(default = "default_watch_excludes")]
+  pub exclude: Vec<String>,
+
+  // Polls for changes instead of relying on inotify events,
+  // which are not delivered on NFS and some bind mounts.
+  #[serde(default)]
+  pub use_polling: bool,
+
+  // Polling interval in milliseconds.
+  #[serde(default = "default_watch_interval")]
+  pub interval: u64,
+
+  // Debounce timeout in milliseconds.
+  #[serde(default = "default_watch_debounce")]
+  pub debounce: u64,
+}
+
+impl Default for WatchOptions {
   fn default() -> Self {
-    let to_vec = |v: &'static [&'static str]| {
-      v.iter().map(|&s| s.to_owned()).collect()
-    };
+    WatchOptions {
+      enabled: None,
+      exclude: default_watch_excludes(),
+      use_polling: false,
+      interval: default_watch_interval(),
+      debounce: default_watch_debounce(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RerunTrigger {
+  // Reruns every test file.
+  Pattern(String),
+  // Reruns only the test files matching `tests`.
+  Scoped { pattern: String, tests: Vec<String> },
+}
+
+impl RerunTrigger {
+  pub fn pattern(&self) -> &str {
+    match self {
+      RerunTrigger::Pattern(pattern) => pattern,
+      RerunTrigger::Scoped { pattern, .. } => pattern,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct SequenceConfig {
+  #[serde(default)]
+  pub shuffle: Option<ShuffleConfig>,
+
+  // Random when omitted, printed in the summary to reproduce an order.
+  #[serde(default)]
+  pub seed: Option<u64>,
+
+  #[serde(default)]
+  pub hooks: Option<HookSequence>,
+}
+
+// Order of the hooks of one kind within a suite.
+#[derive(
+  Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HookSequence {
+  // Registration order.
+  #[default]
+  List,
+  // After hooks run in reverse registration order.
+  Stack,
+  // Async hooks run concurrently on the event loop.
+  Parallel,
+}
+
+// Either `shuffle: true` or `shuffle: { files: true, ... }`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ShuffleConfig {
+  Enabled(bool),
+  Options(ShuffleOptions),
+}
 
+#[derive(
+  Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(deny_unknown_fields)]
+pub struct ShuffleOptions {
+  #[serde(default)]
+  pub files: bool,
+
+  #[serde(default)]
+  pub suites: bool,
+
+  #[serde(default)]
+  pub tasks: bool,
+}
+
+impl ShuffleConfig {
+  pub fn options(&self) -> ShuffleOptions {
+    match self {
+      ShuffleConfig::Enabled(enabled) => ShuffleOptions {
+        files: *enabled,
+        suites: *enabled,
+        tasks: *enabled,
+      },
+      ShuffleConfig::Options(options) => *options,
+    }
+  }
+}
+
+impl ShuffleOptions {
+  pub fn is_enabled(&self) -> bool {
+    self.files || self.suites || self.tasks
+  }
+}
+
+// Reporters run once the test run has finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReporterKind {
+  Default,
+  // Prints the run report as JSON.
+  Json,
+  // Writes the run report to `.kurtex-reports` for `merge-reports`.
+  Blob,
+}
+
+impl std::str::FromStr for ReporterKind {
+  type Err = AnyError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "default" => Ok(ReporterKind::Default),
+      "json" => Ok(ReporterKind::Json),
+      "blob" => Ok(ReporterKind::Blob),
+      _ => Err(anyhow!(
+        "Unknown reporter '{}', expected one of: default, json, blob",
+        s
+      )),
+    }
+  }
+}
+
+impl std::str::FromStr for HookSequence {
+  type Err = AnyError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "list" => Ok(HookSequence::List),
+      "stack" => Ok(HookSequence::Stack),
+      "parallel" => Ok(HookSequence::Parallel),
+      _ => Err(anyhow!(
+        "Unknown hook sequence '{}', expected one of: list, stack, parallel",
+        s
+      )),
+    }
+  }
+}
+
+impl Default for KurtexConfig {
+  fn default() -> Self {
     KurtexConfig {
-      includes: to_vec(DEFAULT_INCLUDES),
-      excludes: to_vec(DEFAULT_EXCLUDES),
+      includes: to_owned_vec(DEFAULT_INCLUDES),
+      excludes: to_owned_vec(DEFAULT_EXCLUDES),
       watch: None,
       parallel: None,
+      force_rerun_triggers: Vec::new(),
+      reporters: Vec::new(),
+      sequence: None,
+      retry: None,
+      repeats: None,
+      bail: None,
+      pass_with_no_tests: None,
+      global_setup: Vec::new(),
+      setup_files: Vec::new(),
+      clear_mocks: None,
+      reset_mocks: None,
+      restore_mocks: None,
     }
   }
 }
 
+fn to_owned_vec(v: &'static [&'static str]) -> Vec<String> {
+  v.iter().map(|&s| s.to_owned()).collect()
+}
+
+fn default_watch_excludes() -> Vec<String> {
+  to_owned_vec(DEFAULT_WATCH_EXCLUDES)
+}
+
+fn default_watch_interval() -> u64 {
+  DEFAULT_WATCH_INTERVAL
+}
+
+fn default_watch_debounce() -> u64 {
+  DEFAULT_WATCH_DEBOUNCE
+}
+
 const DEFAULT_INCLUDES: &'static [&'static str] =
   &["**/*.{test,spec}.{js,mjs,cjs,ts,mts,cts,jsx,tsx}"];
 
 const DEFAULT_EXCLUDES: &'static [&'static str] =
   &["**/node_modules/**", "**/dist/**"];
 
+const DEFAULT_WATCH_EXCLUDES: &'static [&'static str] =
+  &["**/node_modules/**", "**/.git/**", "**/dist/**", "**/target/**"];
+
+const DEFAULT_WATCH_INTERVAL: u64 = 100;
+
+const DEFAULT_WATCH_DEBOUNCE: u64 = 1500;
+
 impl ConfigLoader {
   pub fn new<S>(config_path: S) -> Self
   where
@@ -70,12 +318,27 @@ impl ConfigLoader {
   }
 
   pub async fn load(&self) -> AnyResult<KurtexConfig> {
+    let (config, _) = self.load_with_dependencies().await?;
+
+    Ok(config)
+  }
+
+  // Loads the config along with every local file it imports,
+  // so watch mode knows which changes require a reload.
+  pub async fn load_with_dependencies(
+    &self,
+  ) -> AnyResult<(KurtexConfig, Vec<PathBuf>)> {
     let path_exists = Path::new(self.config_path.deref()).exists();
 
     assert!(path_exists, "Config path not found.");
 
     match self.resolve_config_extension()? {
-      ConfigExtension::Json => self.parse_json_file(),
+      ConfigExtension::Json => {
+        let config_path = PathBuf::from(self.config_path.as_ref());
+        let dependencies = vec![canonicalize_path(config_path)];
+
+        Ok((self.parse_json_file()?, dependencies))
+      }
       ConfigExtension::JavaScript | ConfigExtension::TypeScript => {
         self.parse_esm_file().await
       }
@@ -113,16 +376,60 @@ impl ConfigLoader {
       .map_err(|_| anyhow!("Failed to deserialize config file (json)"))
   }
 
-  async fn parse_esm_file(&self) -> AnyResult<KurtexConfig> {
+  async fn parse_esm_file(
+    &self,
+  ) -> AnyResult<(KurtexConfig, Vec<PathBuf>)> {
     let mut runtime =
       KurtexRuntime::new(KurtexRuntimeOptions::default());
 
-    let module_id = runtime.resolve_module(&self.config_path).await?;
+    let module_id =
+      runtime.resolve_test_module(&self.config_path).await?;
+    let dependencies = runtime
+      .build_graph()
+      .await
+      .modules()
+      .filter_map(|module| module.specifier().to_file_path().ok())
+      .map(canonicalize_path)
+      .collect();
+
     let (exports, scope) = runtime
       .get_module_exports::<v8::Local<v8::Object>, &str>(module_id, None)
       .await?;
-    KurtexRuntime::serialize_v8_object::<KurtexConfig>(scope, exports)
-      .await
-      .map_err(|_| anyhow!("Failed to parse config: Invalid settings"))
+    let config =
+      KurtexRuntime::serialize_v8_object::<KurtexConfig>(scope, exports)
+        .await
+        .map_err(|_| {
+          anyhow!("Failed to parse config: Invalid settings")
+        })?;
+
+    Ok((config, dependencies))
+  }
+}
+
+fn canonicalize_path(path: PathBuf) -> PathBuf {
+  fs::canonicalize(&path).unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::HookSequence;
+
+  #[test]
+  fn test_hook_sequence_from_str() {
+    assert_eq!(
+      "list".parse::<HookSequence>().unwrap(),
+      HookSequence::List
+    );
+    assert_eq!(
+      "stack".parse::<HookSequence>().unwrap(),
+      HookSequence::Stack
+    );
+    assert_eq!(
+      "parallel".parse::<HookSequence>().unwrap(),
+      HookSequence::Parallel
+    );
+
+    let error = "Stack".parse::<HookSequence>().unwrap_err();
+    assert!(error.to_string().contains("expected one of"));
   }
 }
@@ -1,12 +1,15 @@
 use rccell::RcCell;
 use std::borrow::Cow;
 
+use deno_core::{v8, ModuleSpecifier};
+
 use crate::collector::{
-  CollectorContext, CollectorIdentifier, CollectorMode, LifetimeHook,
-  NodeCollectorManager,
+  CollectorContext, CollectorIdentifier, CollectorLocation,
+  CollectorMode, LifetimeHook, NodeCollectorManager,
 };
 use crate::deno::ExtensionLoader;
-use crate::{CollectorMetadata, TestCallback};
+use crate::runner::global_setup::ProvidedContext;
+use crate::{CollectorMetadata, TaskOptions, TestCallback};
 
 // Kurtex V8 -> Rust interface layer.
 pub struct CollectorRegistryExt;
@@ -20,31 +23,41 @@ impl CollectorRegistryExt {
   #[meta(sanitizer_details = "register new task unit")]
   #[meta(sanitizer_fix = "awaiting identifier and callback")]
   fn op_register_collector_task(
+    scope: &mut v8::HandleScope,
     #[state] collector_ctx: &CollectorContext,
     #[string] identifier: String,
     #[from_v8] callback: TestCallback,
     #[from_v8] run_mode: CollectorMode,
+    #[serde] options: TaskOptions,
   ) {
+    let location = caller_location(scope);
+
     collector_ctx
       .get_current()
       .borrow_mut()
-      .register_task(identifier, callback, run_mode)
+      .register_task(identifier, callback, run_mode, location, options)
   }
 
   #[deno_core::op2]
   #[meta(sanitizer_details = "register new test node (suite)")]
   #[meta(sanitizer_fix = "awaiting identifier and callback")]
   fn op_register_collector_node(
+    scope: &mut v8::HandleScope,
     #[state] collector_ctx: &mut CollectorContext,
     #[from_v8] identifier: CollectorIdentifier,
     #[from_v8] factory: TestCallback,
     #[from_v8] run_mode: CollectorMode,
   ) {
-    collector_ctx.register_collector(RcCell::new(
-      NodeCollectorManager::new_with_factory(
-        identifier, run_mode, factory,
-      ),
-    ));
+    let location = caller_location(scope);
+    let parent = collector_ctx.get_current();
+    let collector = NodeCollectorManager::new_with_factory(
+      identifier, run_mode, factory,
+    )
+    .with_parent(&parent.borrow())
+    .with_location(location);
+
+    parent.borrow_mut().register_child(&collector);
+    collector_ctx.register_collector(RcCell::new(collector));
   }
 
   #[deno_core::op2]
@@ -60,6 +73,15 @@ impl CollectorRegistryExt {
       .borrow_mut()
       .register_lifetime_hook(lifetime_hook, callback);
   }
+
+  #[deno_core::op2]
+  #[serde]
+  fn op_inject(
+    #[state] provided: &ProvidedContext,
+    #[string] key: String,
+  ) -> Option<serde_json::Value> {
+    provided.get(&key).cloned()
+  }
 }
 
 impl ExtensionLoader for CollectorRegistryExt {
@@ -72,13 +94,15 @@ impl ExtensionLoader for CollectorRegistryExt {
         let collector_meta = CollectorMetadata::default();
 
         op_state.put(collector_ctx);
-        op_state.put(collector_meta)
+        op_state.put(collector_meta);
+        op_state.put(ProvidedContext::default())
       });
 
     let collector_registry_ops: Vec<deno_core::OpDecl> = vec![
       Self::op_register_collector_task,
       Self::op_register_collector_node,
       Self::op_register_lifetime_hook,
+      Self::op_inject,
     ]
     .iter()
     .map(|cb| cb())
@@ -93,3 +117,29 @@ impl ExtensionLoader for CollectorRegistryExt {
     }
   }
 }
+
+// First stack frame that belongs to a user module, the runtime
+// bindings live under `ext:` and are skipped. Positions point to the
+// emitted code and are mapped back once the file is collected.
+fn caller_location(
+  scope: &mut v8::HandleScope,
+) -> Option<CollectorLocation> {
+  const MAX_FRAMES: usize = 16;
+
+  let stack_trace =
+    v8::StackTrace::current_stack_trace(scope, MAX_FRAMES)?;
+
+  (0..stack_trace.get_frame_count()).find_map(|index| {
+    let frame = stack_trace.get_frame(scope, index)?;
+    let script_name = frame.get_script_name(scope)?;
+    let script_name = script_name.to_rust_string_lossy(scope);
+    let file =
+      ModuleSpecifier::parse(&script_name).ok()?.to_file_path().ok()?;
+
+    Some(CollectorLocation {
+      file,
+      line: frame.get_line_number() as u32,
+      column: frame.get_column() as u32,
+    })
+  })
+}
@@ -11,17 +11,19 @@ use deno_core::v8::{DataError, HandleScope, Local, Value};
 use deno_core::{v8, CrossIsolateStore, ModuleId, PollEventLoopOptions};
 use deno_graph::{BuildOptions, GraphKind, ModuleGraph, WalkOptions};
 use hashbrown::HashMap;
+use log::debug;
 use rccell::RcCell;
 use serde::{Deserialize, Serialize};
 
 use kurtex_binding::ts_module_loader::TypescriptModuleLoader;
 
 use crate::deno::ExtensionLoader;
-use crate::AnyResult;
+use crate::{AnyResult, CollectorLocation};
 
 pub struct KurtexRuntime {
   runtime: deno_core::JsRuntime,
   module_map: HashMap<ModuleId, ModuleSpecifier>,
+  module_loader: Rc<TypescriptModuleLoader>,
   graph: KurtexGraph,
 }
 
@@ -54,7 +56,12 @@ impl KurtexRuntime {
       });
     let graph = KurtexGraph::new(module_loader.clone());
 
-    Self { runtime: deno_runtime, graph, module_map: Default::default() }
+    Self {
+      runtime: deno_runtime,
+      graph,
+      module_loader,
+      module_map: Default::default(),
+    }
   }
 
   pub async fn resolve_module<S>(
@@ -235,13 +242,109 @@ impl KurtexRuntime {
     &mut self,
     callback: &'a v8::Global<v8::Function>,
   ) -> AnyResult<v8::Global<v8::Value>> {
-    let call = self.runtime.call_with_args(callback, &[]);
+    self.call_v8_function_with_args(callback, &[]).await
+  }
+
+  pub async fn call_v8_function_with_args<'a>(
+    &mut self,
+    callback: &'a v8::Global<v8::Function>,
+    args: &[v8::Global<v8::Value>],
+  ) -> AnyResult<v8::Global<v8::Value>> {
+    let call = self.runtime.call_with_args(callback, args);
     self
       .runtime
       .with_event_loop_promise(call, PollEventLoopOptions::default())
       .await
   }
 
+  pub fn execute_script(
+    &mut self,
+    name: &'static str,
+    source: &'static str,
+  ) -> AnyResult<v8::Global<v8::Value>> {
+    self.runtime.execute_script(name, source)
+  }
+
+  // Export of an evaluated module, `None` unless it is a function.
+  pub fn exported_function(
+    &mut self,
+    module_id: ModuleId,
+    export_name: &str,
+  ) -> AnyResult<Option<v8::Global<v8::Function>>> {
+    let namespace = self.runtime.get_module_namespace(module_id)?;
+    let scope = &mut self.runtime.handle_scope();
+    let namespace = v8::Local::new(scope, namespace);
+    let export_name = v8::String::new(scope, export_name).unwrap();
+
+    let function = namespace
+      .get(scope, export_name.into())
+      .and_then(|value| Local::<v8::Function>::try_from(value).ok());
+
+    Ok(function.map(|function| v8::Global::new(scope, function)))
+  }
+
+  pub fn serialize_to_v8<T>(
+    &mut self,
+    value: &T,
+  ) -> AnyResult<v8::Global<v8::Value>>
+  where
+    T: Serialize,
+  {
+    let scope = &mut self.runtime.handle_scope();
+    let value = deno_core::serde_v8::to_v8(scope, value)?;
+
+    Ok(v8::Global::new(scope, value))
+  }
+
+  pub fn deserialize_v8_value<R>(
+    &mut self,
+    value: &v8::Global<v8::Value>,
+  ) -> AnyResult<R>
+  where
+    R: for<'de> Deserialize<'de>,
+  {
+    let scope = &mut self.runtime.handle_scope();
+    let value = Local::new(scope, value);
+
+    Ok(deno_core::serde_v8::from_v8(scope, value)?)
+  }
+
+  // Calls every function before awaiting any of them, so async
+  // callbacks make progress concurrently on the event loop.
+  pub async fn call_v8_functions_concurrently<'a>(
+    &mut self,
+    callbacks: impl IntoIterator<Item = &'a v8::Global<v8::Function>>,
+  ) -> Vec<AnyResult<v8::Global<v8::Value>>> {
+    let calls = callbacks
+      .into_iter()
+      .map(|callback| self.runtime.call_with_args(callback, &[]))
+      .collect::<Vec<_>>();
+    let mut results = Vec::with_capacity(calls.len());
+
+    for call in calls {
+      let result = self
+        .runtime
+        .with_event_loop_promise(call, PollEventLoopOptions::default())
+        .await;
+
+      results.push(result);
+    }
+
+    results
+  }
+
+  // A function returned from a callback, e.g. the cleanup of a hook.
+  pub fn as_v8_function(
+    &mut self,
+    value: &v8::Global<v8::Value>,
+  ) -> Option<v8::Global<v8::Function>> {
+    let scope = &mut self.runtime.handle_scope();
+    let value = Local::new(scope, value);
+    let function = Local::<v8::Function>::try_from(value).ok()?;
+
+    Some(v8::Global::new(scope, function))
+  }
+
   pub async fn serialize_v8_object<R>(
     mut scope: HandleScope<'_>,
     v8_object: Local<'_, v8::Object>,
@@ -252,6 +355,26 @@ impl KurtexRuntime {
     Ok(deno_core::serde_v8::from_v8(&mut scope, v8_object.into())?)
   }
 
+  // Resolves a location captured from emitted code to the position
+  // in the original test file.
+  pub fn map_location(&self, location: &mut CollectorLocation) {
+    let Ok(specifier) = ModuleSpecifier::from_file_path(&location.file)
+    else {
+      return;
+    };
+
+    let original_position = self.module_loader.original_position(
+      specifier.as_str(),
+      location.line,
+      location.column,
+    );
+
+    if let Some((line, column)) = original_position {
+      location.line = line;
+      location.column = column;
+    }
+  }
+
   pub async fn build_graph(&self) -> Rc<ModuleGraph> {
     self.graph.build().await.unwrap()
   }
@@ -272,8 +395,11 @@ impl KurtexGraph {
     }
   }
 
+  // Setup files are added again for every test file.
   fn add_root(&mut self, specifier: ModuleSpecifier) {
-    self.roots.push(specifier)
+    if !self.roots.contains(&specifier) {
+      self.roots.push(specifier)
+    }
   }
 
   pub async fn build(&self) -> AnyResult<Rc<ModuleGraph>> {
@@ -291,9 +417,11 @@ impl KurtexGraph {
       .build(roots.clone(), loader.deref(), BuildOptions::default())
       .await;
 
-    println!("{:#?}", graph);
+    debug!("{:#?}", graph);
 
-    graph
+    // Missing or broken modules, e.g. an import of a deleted file,
+    // still leave a graph that resolves importers.
+    let validate_result = graph
       .walk(
         roots.iter(),
         WalkOptions {
@@ -303,8 +431,11 @@ impl KurtexGraph {
           prefer_fast_check_graph: false,
         },
       )
-      .validate()
-      .unwrap();
+      .validate();
+
+    if let Err(error) = validate_result {
+      debug!("Module graph error: {}", error);
+    }
 
     *built = true;
     Ok(Rc::new(graph))
@@ -0,0 +1,362 @@
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use anyhow::{anyhow, bail};
+use hashbrown::HashSet;
+
+use crate::runner::collector::{
+  FileCollector, FileCollectorOptions, TestRunnerConfig,
+};
+use crate::runner::durations::relative_key;
+use crate::runner::runner::TestRunner;
+use crate::runner::store::TaskSelection;
+use crate::runner::{create_runtime, launch_runner, EmitRuntimeOptions};
+use crate::{AnyResult, CollectorStatus};
+
+// A test addressed as `path/to/file.test.ts > suite > name`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskId {
+  pub file: PathBuf,
+  pub task_path: String,
+}
+
+impl std::fmt::Display for TaskId {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{} > {}", self.file.display(), self.task_path)
+  }
+}
+
+pub enum BisectResult {
+  // The target fails even when it runs alone.
+  FailsAlone,
+  // The target passes after all the tests that precede it.
+  NotReproduced,
+  // Running this earlier test first makes the target fail.
+  Culprit(TaskId),
+  // The failure needs several of these tests together.
+  Culprits(Vec<TaskId>),
+}
+
+// Outcome of a bisect along with what it took to get there.
+pub struct BisectReport {
+  pub result: BisectResult,
+  // Number of runs it took, including the two confirming runs.
+  pub probes: usize,
+  pub seed: Option<u64>,
+}
+
+// Reruns the target after a subset of the preceding tests.
+trait Probe {
+  async fn fails_after(
+    &mut self,
+    preceding: &[TaskId],
+  ) -> AnyResult<bool>;
+}
+
+struct Bisector<F> {
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+  target: TaskId,
+  probes: usize,
+  on_probe: F,
+}
+
+// Reruns subsets of the tests preceding `target_id` in fresh
+// runtimes to find the earlier test whose side effects make it fail.
+// `on_probe` is called before each run with its number and the count
+// of preceding tests.
+pub async fn bisect(
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+  target_id: &str,
+  on_probe: impl FnMut(usize, usize),
+) -> AnyResult<BisectReport> {
+  let run_order =
+    collect_run_order(config.clone(), emit_opts.clone()).await?;
+  let target = resolve_target(&run_order, target_id, &config)?;
+  let position =
+    run_order.iter().position(|task| *task == target).unwrap();
+  let preceding = run_order[..position].to_vec();
+  let seed = config.shuffle_seed();
+
+  // Bailing on an earlier failure would hide the target's result.
+  let config =
+    Rc::new(TestRunnerConfig { bail: None, ..(*config).clone() });
+  let mut bisector =
+    Bisector { config, emit_opts, target, probes: 0, on_probe };
+  let result = search(&mut bisector, preceding).await?;
+
+  Ok(BisectReport { result, probes: bisector.probes, seed })
+}
+
+async fn search(
+  probe: &mut impl Probe,
+  preceding: Vec<TaskId>,
+) -> AnyResult<BisectResult> {
+  if probe.fails_after(&[]).await? {
+    return Ok(BisectResult::FailsAlone);
+  }
+
+  if !probe.fails_after(&preceding).await? {
+    return Ok(BisectResult::NotReproduced);
+  }
+
+  // Narrow down to the files first, it takes fewer probes than
+  // bisecting every preceding test right away.
+  let mut files =
+    preceding.iter().map(|task| task.file.clone()).collect::<Vec<_>>();
+  files.dedup();
+
+  let file_groups = files
+    .into_iter()
+    .map(|file| {
+      preceding
+        .iter()
+        .filter(|task| task.file == file)
+        .cloned()
+        .collect::<Vec<_>>()
+    })
+    .collect::<Vec<_>>();
+
+  let culprit_groups = narrow(probe, file_groups).await?;
+  let candidates =
+    culprit_groups.into_iter().flatten().collect::<Vec<_>>();
+  let candidates =
+    candidates.into_iter().map(|task| vec![task]).collect();
+  let culprits =
+    narrow(probe, candidates).await?.into_iter().flatten().collect();
+  let mut culprits = minimize(probe, culprits).await?;
+
+  if culprits.len() == 1 {
+    Ok(BisectResult::Culprit(culprits.remove(0)))
+  } else {
+    Ok(BisectResult::Culprits(culprits))
+  }
+}
+
+// Halves `groups` while one half alone still makes the target
+// fail. Stops when the failure needs tests from both halves.
+async fn narrow(
+  probe: &mut impl Probe,
+  mut groups: Vec<Vec<TaskId>>,
+) -> AnyResult<Vec<Vec<TaskId>>> {
+  while groups.len() > 1 {
+    let second_half = groups.split_off(groups.len() / 2);
+
+    if probe.fails_after(&groups.concat()).await? {
+      continue;
+    }
+
+    if probe.fails_after(&second_half.concat()).await? {
+      groups = second_half;
+      continue;
+    }
+
+    groups.extend(second_half);
+    break;
+  }
+
+  Ok(groups)
+}
+
+// Halving stops at the first split that separates two culprits, so
+// the remaining set may still hold tests the failure doesn't need.
+// Drops them one at a time.
+async fn minimize(
+  probe: &mut impl Probe,
+  mut culprits: Vec<TaskId>,
+) -> AnyResult<Vec<TaskId>> {
+  let mut index = 0;
+
+  while culprits.len() > 1 && index < culprits.len() {
+    let mut without = culprits.clone();
+    without.remove(index);
+
+    if probe.fails_after(&without).await? {
+      culprits = without;
+    } else {
+      index += 1;
+    }
+  }
+
+  Ok(culprits)
+}
+
+impl<F: FnMut(usize, usize)> Probe for Bisector<F> {
+  async fn fails_after(
+    &mut self,
+    preceding: &[TaskId],
+  ) -> AnyResult<bool> {
+    self.probes += 1;
+    (self.on_probe)(self.probes, preceding.len());
+
+    let mut files = Vec::new();
+    let mut only_tasks = TaskSelection::new();
+
+    for task in preceding.iter().chain([&self.target]) {
+      if !files.contains(&task.file) {
+        files.push(task.file.clone());
+      }
+
+      only_tasks
+        .entry(task.file.clone())
+        .or_insert_with(HashSet::new)
+        .insert(task.task_path.clone());
+    }
+
+    let runtime = create_runtime(self.emit_opts.clone())?;
+    let collector_opts = FileCollectorOptions {
+      existing_paths: Some(files),
+      only_tasks: Some(only_tasks),
+    };
+    let (_, ctx) =
+      launch_runner(runtime, self.config.clone(), collector_opts)
+        .await?;
+    let ctx = ctx.borrow();
+
+    let file = ctx
+      .file_map
+      .get(&self.target.file)
+      .ok_or_else(|| anyhow!("{} was not collected", self.target))?;
+
+    if file.error.is_some() {
+      bail!("Failed to collect {}", self.target.file.display());
+    }
+
+    let failed = file.nodes.iter().any(|node| {
+      let node = node.lock().unwrap();
+
+      node.tasks.iter().any(|task| {
+        let task = task.lock().unwrap();
+
+        node.task_path(&task) == self.target.task_path
+          && task.status == CollectorStatus::Fail
+      })
+    });
+
+    Ok(failed)
+  }
+}
+
+async fn collect_run_order(
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+) -> AnyResult<Vec<TaskId>> {
+  let runtime = create_runtime(emit_opts)?;
+  let collector = FileCollector::new(config.clone(), runtime.clone());
+  let ctx = collector.run(Default::default()).await?;
+  let test_runner = TestRunner::new(ctx.clone(), config, runtime);
+
+  let run_order = test_runner
+    .run_order(&ctx.borrow())
+    .into_iter()
+    .map(|(file, task_path)| TaskId { file, task_path })
+    .collect();
+
+  Ok(run_order)
+}
+
+fn resolve_target(
+  run_order: &[TaskId],
+  target_id: &str,
+  config: &TestRunnerConfig,
+) -> AnyResult<TaskId> {
+  let (file, task_path) =
+    target_id.split_once(" > ").ok_or_else(|| {
+      anyhow!("Expected a test id like 'file.test.ts > suite > name'")
+    })?;
+  let file = file.trim_start_matches("./");
+
+  run_order
+    .iter()
+    .find(|task| {
+      task.task_path == task_path
+        && relative_key(&task.file, &config.root_dir) == file
+    })
+    .cloned()
+    .ok_or_else(|| anyhow!("No runnable test matches '{}'", target_id))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use crate::runner::bisect::{search, BisectResult, Probe, TaskId};
+  use crate::AnyResult;
+
+  // Fails the target when every test in `culprits` ran before it.
+  struct FakeProbe {
+    culprits: Vec<TaskId>,
+    fails_alone: bool,
+  }
+
+  impl Probe for FakeProbe {
+    async fn fails_after(
+      &mut self,
+      preceding: &[TaskId],
+    ) -> AnyResult<bool> {
+      Ok(
+        self.fails_alone
+          || (!self.culprits.is_empty()
+            && self
+              .culprits
+              .iter()
+              .all(|task| preceding.contains(task))),
+      )
+    }
+  }
+
+  fn task(file: &str, task_path: &str) -> TaskId {
+    TaskId { file: PathBuf::from(file), task_path: task_path.into() }
+  }
+
+  fn run_order() -> Vec<TaskId> {
+    ["a", "b", "c", "d"]
+      .into_iter()
+      .flat_map(|file| {
+        (0..3).map(move |index| task(file, &format!("test {}", index)))
+      })
+      .collect()
+  }
+
+  async fn run_search(
+    culprits: Vec<TaskId>,
+    fails_alone: bool,
+  ) -> BisectResult {
+    let mut probe = FakeProbe { culprits, fails_alone };
+
+    search(&mut probe, run_order()).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_bisect_culprit() {
+    let culprit = task("c", "test 1");
+
+    match run_search(vec![culprit.clone()], false).await {
+      BisectResult::Culprit(found) => assert_eq!(found, culprit),
+      _ => panic!("expected a single culprit"),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_bisect_minimizes_culprits() {
+    let culprits = vec![task("a", "test 2"), task("d", "test 0")];
+
+    match run_search(culprits.clone(), false).await {
+      BisectResult::Culprits(found) => assert_eq!(found, culprits),
+      _ => panic!("expected several culprits"),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_bisect_not_order_dependent() {
+    assert!(matches!(
+      run_search(vec![], true).await,
+      BisectResult::FailsAlone
+    ));
+    assert!(matches!(
+      run_search(vec![], false).await,
+      BisectResult::NotReproduced
+    ));
+  }
+}
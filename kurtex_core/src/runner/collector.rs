@@ -1,13 +1,16 @@
+use anyhow::Context;
 use std::fmt::Formatter;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use deno_core::error::AnyError;
 use hashbrown::HashMap;
 use log::debug;
 use rayon::prelude::*;
 use rccell::RcCell;
+use regex::Regex;
 
 use crate::collector::context::{CollectorContext, CollectorMetadata};
 use crate::collector::structures::{
@@ -17,29 +20,147 @@ use crate::collector::structures::{
 use crate::deno::runtime::KurtexRuntime;
 use crate::error::AnyResult;
 use crate::reporter::{KurtexDefaultReporter, Reporter};
+use crate::runner::filter::{FileFilter, LocationFilter, TaskFilter};
+use crate::runner::runner::set_mock_file;
+use crate::runner::shard::Shard;
+use crate::runner::store::TaskSelection;
+use crate::util::git::ChangedFilesScope;
+use crate::util::random;
 use crate::walk::Walk;
 use crate::{
-  arc, arc_mut, concurrently, map_pinned_futures, KurtexConfig,
+  arc, arc_mut, concurrently, map_pinned_futures, HookSequence,
+  KurtexConfig, ReporterKind, RerunTrigger, ShuffleOptions, WatchConfig,
+  WatchOptions,
 };
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct TestRunnerConfig {
   pub watch: bool,
   pub globals: bool,
   pub parallel: bool,
   pub config_path: PathBuf,
+  // Config file and the local modules it imports.
+  pub config_dependencies: Vec<PathBuf>,
   pub root_dir: PathBuf,
   pub includes: Vec<String>,
   pub excludes: Vec<String>,
+  pub watch_options: WatchOptions,
+  pub force_rerun_triggers: Vec<RerunTrigger>,
+  // Only run tests related to files changed in git.
+  pub changed: Option<ChangedFilesScope>,
+  // Substrings or globs a test file path has to match.
+  pub file_filters: Vec<String>,
+  // Matched against the full suite path of every test.
+  pub test_name_pattern: Option<Regex>,
+  // `file:line` filters selecting the test or suite at a line.
+  pub location_filters: Vec<LocationFilter>,
+  pub shard: Option<Shard>,
+  // Durations of previous runs, used to balance shards.
+  pub durations_path: Option<PathBuf>,
+  pub reporters: Vec<ReporterKind>,
+  pub shuffle: ShuffleOptions,
+  pub seed: Option<u64>,
+  pub hook_sequence: Option<HookSequence>,
+  pub retry: Option<u32>,
+  pub repeats: Option<u32>,
+  // Stop the run once this many tests have failed.
+  pub bail: Option<u32>,
+  // Finish successfully when no tests were found.
+  pub pass_with_no_tests: bool,
+  pub global_setup: Vec<PathBuf>,
+  pub setup_files: Vec<PathBuf>,
+  pub clear_mocks: bool,
+  pub reset_mocks: bool,
+  pub restore_mocks: bool,
+  // Command line options alone, a reloaded config file is merged
+  // onto these instead of the previous file.
+  pub cli_config: Option<Rc<TestRunnerConfig>>,
 }
 
 impl TestRunnerConfig {
   pub fn adjust_config_file(&mut self, config: KurtexConfig) {
+    if self.cli_config.is_none() {
+      self.cli_config = Some(Rc::new(self.clone()));
+    }
+
     config.parallel.map(|par| self.parallel = par);
-    config.watch.map(|watch| self.watch = watch);
+
+    match config.watch {
+      Some(WatchConfig::Enabled(watch)) => self.watch = watch,
+      Some(WatchConfig::Options(options)) => {
+        options.enabled.map(|watch| self.watch = watch);
+        self.watch_options = options;
+      }
+      None => {}
+    }
 
     self.includes = config.includes;
     self.excludes = config.excludes;
+    self.force_rerun_triggers = config.force_rerun_triggers;
+    self.global_setup = self.resolve_root_paths(&config.global_setup);
+    self.setup_files = self.resolve_root_paths(&config.setup_files);
+    self.retry = self.retry.or(config.retry);
+    self.repeats = self.repeats.or(config.repeats);
+    self.bail = self.bail.or(config.bail);
+    config
+      .pass_with_no_tests
+      .map(|pass| self.pass_with_no_tests |= pass);
+    config.clear_mocks.map(|clear| self.clear_mocks = clear);
+    config.reset_mocks.map(|reset| self.reset_mocks = reset);
+    config.restore_mocks.map(|restore| self.restore_mocks = restore);
+
+    // Reporters passed on the command line take precedence.
+    if self.reporters.is_empty() {
+      self.reporters = config.reporters;
+    }
+
+    if let Some(sequence) = config.sequence {
+      if let Some(shuffle) =
+        sequence.shuffle.filter(|_| !self.shuffle.is_enabled())
+      {
+        self.shuffle = shuffle.options();
+      }
+
+      self.seed = self.seed.or(sequence.seed);
+      self.hook_sequence = self.hook_sequence.or(sequence.hooks);
+    }
+
+    if self.shuffle.is_enabled() && self.seed.is_none() {
+      self.seed = Some(random::random_seed());
+    }
+  }
+
+  // Seed used to shuffle, `None` when running in declaration order.
+  pub fn shuffle_seed(&self) -> Option<u64> {
+    self.seed.filter(|_| self.shuffle.is_enabled())
+  }
+
+  fn resolve_root_paths(&self, paths: &[String]) -> Vec<PathBuf> {
+    paths
+      .iter()
+      // Collecting the components drops `./` segments.
+      .map(|path| self.root_dir.join(path).components().collect())
+      .collect()
+  }
+
+  pub fn hook_sequence(&self) -> HookSequence {
+    self.hook_sequence.unwrap_or_default()
+  }
+
+  // Config to merge a reloaded config file onto.
+  pub fn without_config_file(&self) -> TestRunnerConfig {
+    match &self.cli_config {
+      Some(cli_config) => cli_config.as_ref().clone(),
+      None => self.clone(),
+    }
+  }
+
+  pub fn reporters(&self) -> Vec<ReporterKind> {
+    if self.reporters.is_empty() {
+      vec![ReporterKind::Default]
+    } else {
+      self.reporters.clone()
+    }
   }
 }
 
@@ -59,6 +180,11 @@ pub struct RunnerCollectorContext {
   pub file_map: CollectorFileMap,
   pub nodes: Vec<Arc<Mutex<CollectorNode>>>,
   pub tasks: Vec<Arc<Mutex<CollectorTask>>>,
+  pub file_durations: HashMap<PathBuf, Duration>,
+  // Printed in the summary when the run order was shuffled.
+  pub seed: Option<u64>,
+  // Set when the run stopped early because of `bail`.
+  pub bailed: bool,
   pub reporter: KurtexDefaultReporter,
   pub state: RunnerContextState,
 }
@@ -78,9 +204,10 @@ pub struct FileCollector {
   runtime: RcCell<KurtexRuntime>,
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct FileCollectorOptions {
   pub(crate) existing_paths: Option<Vec<PathBuf>>,
+  pub(crate) only_tasks: Option<TaskSelection>,
 }
 
 impl FileCollector {
@@ -100,27 +227,63 @@ impl FileCollector {
       file_path: PathBuf,
       runtime: RcCell<KurtexRuntime>,
       collector_ctx: RcCell<RunnerCollectorContext>,
+      config: Rc<TestRunnerConfig>,
     ) -> AnyResult<Arc<CollectorFile>> {
-      let obtained_collectors = {
+      let setup_error = {
         let mut runtime = runtime.borrow_mut();
 
         runtime.mutate_state(|ctx: &mut CollectorContext| {
           *ctx = Default::default();
         })?;
 
-        #[allow(unused)]
-        let _ = runtime
-          .resolve_test_module(file_path.display().to_string())
-          .await;
+        // Evaluated again for every test file, so the hooks they
+        // register land on the root node of that file.
+        let setup_result: AnyResult = try {
+          set_mock_file(&mut runtime, &file_path, true).await?;
+
+          for setup_file in config.setup_files.iter() {
+            let setup_file = setup_file.display().to_string();
+            let _ = runtime.remove_from_module_map(&setup_file).await;
+
+            // As a graph root, a change to the setup file or to its
+            // imports reruns every test file in watch mode.
+            runtime
+              .resolve_test_module(&setup_file)
+              .await
+              .with_context(|| {
+                format!("Failed to run setup file {}", setup_file)
+              })?;
+          }
+        };
 
-        runtime
-          .get_state(|ctx: &CollectorContext| ctx.acquire_collectors())?
+        if setup_result.is_ok() {
+          #[allow(unused)]
+          let _ = runtime
+            .resolve_test_module(file_path.display().to_string())
+            .await;
+        }
+
+        setup_result.err()
       };
 
       let mut collector_file = CollectorFile::from_path(file_path);
+      collector_file.error = setup_error;
+      let mut collector_index = 0;
 
-      for collector in obtained_collectors {
+      // Suites registered by a factory are appended to the collectors,
+      // nested suites are collected after their parent.
+      loop {
         let mut runtime = runtime.borrow_mut();
+        let collector =
+          runtime.get_state(|ctx: &CollectorContext| {
+            ctx.collector_at(collector_index)
+          })?;
+
+        let Some(collector) = collector else {
+          break;
+        };
+        collector_index += 1;
+
         let mut collector_ctx = collector_ctx.borrow_mut();
 
         runtime.mutate_state_with(
@@ -146,7 +309,19 @@ impl FileCollector {
           }
         }
         let collected_node_rc = collector.borrow_mut().collect_node();
-        let collected_node = collected_node_rc.lock().unwrap();
+        let mut collected_node = collected_node_rc.lock().unwrap();
+
+        if let Some(location) = collected_node.location.as_mut() {
+          runtime.map_location(location);
+        }
+
+        for task in &collected_node.tasks {
+          let mut task = task.lock().unwrap();
+
+          if let Some(location) = task.location.as_mut() {
+            runtime.map_location(location);
+          }
+        }
 
         runtime.mutate_state_with(
           &collected_node,
@@ -193,14 +368,14 @@ impl FileCollector {
 
     let processed_files = map_pinned_futures!(
       target_files,
-      process_test_file(runtime, collector_ctx),
+      process_test_file(runtime, collector_ctx, config),
       {
         runtime = self.runtime.clone()
         collector_ctx = collector_ctx.clone()
+        config = self.config.clone()
       }
     );
 
-
     collector_ctx.borrow_mut().reporter.start();
 
     let mut file_map: CollectorFileMap = if self.config.parallel {
@@ -218,27 +393,48 @@ impl FileCollector {
       file_map
     };
 
+    let task_filter = TaskFilter::new(
+      opts.only_tasks,
+      self.config.test_name_pattern.clone(),
+    )
+    .with_locations(
+      &self.config.location_filters,
+      &file_map,
+      &self.config.root_dir,
+    );
     let mut runtime = self.runtime.borrow_mut();
     runtime.get_state_with(
       &mut file_map,
       |fm, meta: &CollectorMetadata| {
-        Self::normalize_mode_settings(fm, &meta);
+        Self::normalize_mode_settings(fm, &meta, &task_filter);
       },
     )?;
 
     {
       let mut context = collector_ctx.borrow_mut();
       context.file_map = file_map;
+      context.seed = self.config.shuffle_seed();
       context.set_ready();
     }
 
     Ok(collector_ctx)
   }
 
-  fn collect_test_files(opts: &TestRunnerConfig) -> Vec<PathBuf> {
-    let TestRunnerConfig { root_dir, includes, excludes, .. } = opts;
+  pub(crate) fn collect_test_files(
+    opts: &TestRunnerConfig,
+  ) -> Vec<PathBuf> {
+    let TestRunnerConfig {
+      root_dir,
+      includes,
+      excludes,
+      file_filters,
+      location_filters,
+      ..
+    } = opts;
     let included_cases = Walk::new(&includes, root_dir).build();
     let mut excluded_cases = Walk::new(&excludes, root_dir).build();
+    let file_filter =
+      FileFilter::new(file_filters, location_filters, root_dir);
 
     // TODO: rewrite: **/node_modules/**, parallel
     // TODO: build times
@@ -247,16 +443,18 @@ impl FileCollector {
         !excluded_cases
           .any(|excluded_path| excluded_path.eq(included_path))
       })
+      .filter(|included_path| file_filter.includes(included_path))
       .collect()
   }
 
   fn normalize_mode_settings(
     file_map: &mut CollectorFileMap,
     meta: &CollectorMetadata,
+    task_filter: &TaskFilter,
   ) {
     fn interpret_only_mode(target_mode: &mut CollectorMode) {
       let updated_mode = match *target_mode {
-        CollectorMode::Run => CollectorMode::Skip,
+        CollectorMode::Run | CollectorMode::Fails => CollectorMode::Skip,
         CollectorMode::Only => CollectorMode::Run,
         rest => rest,
       };
@@ -294,6 +492,16 @@ impl FileCollector {
             interpret_only_mode(&mut task.mode);
           })
         });
+
+        for task in &node.tasks {
+          let mut task = task.lock().unwrap();
+          let task_path = node.task_path(&task);
+
+          if !task_filter.includes(&file.file_path, &task_path) {
+            task.mode = CollectorMode::Skip;
+            task.status = CollectorStatus::Custom(CollectorMode::Skip);
+          }
+        }
       })
     });
   }
@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::AnyResult;
+
+// Per-file run durations from previous runs, keyed by the path
+// relative to `root_dir` so the store can be shared across machines.
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub struct DurationStore {
+  files: BTreeMap<String, u64>,
+}
+
+impl DurationStore {
+  pub fn load(store_path: &Path) -> AnyResult<Self> {
+    if !store_path.exists() {
+      return Ok(DurationStore::default());
+    }
+
+    let content =
+      std::fs::read_to_string(store_path).with_context(|| {
+        format!("Unable to read durations from {}", store_path.display())
+      })?;
+
+    Ok(serde_json::from_str(&content)?)
+  }
+
+  pub fn save(&self, store_path: &Path) -> AnyResult {
+    if let Some(parent) = store_path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::write(store_path, serde_json::to_string_pretty(self)?)
+      .with_context(|| {
+        format!("Unable to write durations to {}", store_path.display())
+      })
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.files.is_empty()
+  }
+
+  // Duration in milliseconds of the last recorded run.
+  pub fn get(&self, file_path: &Path, root_dir: &Path) -> Option<u64> {
+    self.files.get(&relative_key(file_path, root_dir)).copied()
+  }
+
+  pub fn record(
+    &mut self,
+    durations: &HashMap<PathBuf, Duration>,
+    root_dir: &Path,
+  ) {
+    for (file_path, duration) in durations {
+      self.files.insert(
+        relative_key(file_path, root_dir),
+        duration.as_millis() as u64,
+      );
+    }
+  }
+}
+
+// Root relative path with `/` separators on every platform.
+pub(crate) fn relative_key(file_path: &Path, root_dir: &Path) -> String {
+  let relative_path =
+    file_path.strip_prefix(root_dir).unwrap_or(file_path);
+
+  relative_path
+    .components()
+    .map(|component| component.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/")
+}
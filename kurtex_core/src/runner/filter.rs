@@ -0,0 +1,287 @@
+use std::path::Path;
+
+use hashbrown::HashSet;
+use regex::Regex;
+
+use crate::runner::collector::CollectorFileMap;
+use crate::runner::store::TaskSelection;
+use crate::walk::GlobMatcher;
+use crate::CollectorIdentifier;
+
+// Decides which collected tasks are allowed to run.
+#[derive(Default)]
+pub struct TaskFilter {
+  only_tasks: Option<TaskSelection>,
+  name_pattern: Option<Regex>,
+  located_tasks: Option<TaskSelection>,
+}
+
+// A `path/to/file.test.ts:42` CLI filter.
+#[derive(Debug, Clone)]
+pub struct LocationFilter {
+  pub file: String,
+  pub line: u32,
+}
+
+impl LocationFilter {
+  pub fn parse(filter: &str) -> Option<Self> {
+    let (file, line) = filter.rsplit_once(':')?;
+    let line = line.parse::<u32>().ok()?;
+
+    Some(LocationFilter { file: file.to_owned(), line })
+  }
+
+  // The location names the file or a trailing part of its path,
+  // `a.test.ts` matches `tests/a.test.ts` but not `ba.test.ts`.
+  fn matches_file(&self, file_path: &Path, root_dir: &Path) -> bool {
+    let location_path = Path::new(self.file.trim_start_matches("./"));
+
+    if location_path.is_absolute() {
+      return file_path == location_path;
+    }
+
+    file_path
+      .strip_prefix(root_dir)
+      .unwrap_or(file_path)
+      .ends_with(location_path)
+  }
+}
+
+// Positional CLI filters, a file is kept when any of them is a
+// substring of its relative path, matches it as a glob, or is
+// the file of a `file:line` filter.
+pub(crate) struct FileFilter<'a> {
+  root_dir: &'a Path,
+  patterns: &'a [String],
+  globs: Vec<GlobMatcher>,
+  locations: &'a [LocationFilter],
+}
+
+impl<'a> FileFilter<'a> {
+  pub(crate) fn new(
+    patterns: &'a [String],
+    locations: &'a [LocationFilter],
+    root_dir: &'a Path,
+  ) -> Self {
+    let globs = patterns
+      .iter()
+      .map(|pattern| pattern.trim_start_matches("./"))
+      .filter_map(|pattern| GlobMatcher::new(&[pattern], root_dir).ok())
+      .collect();
+
+    FileFilter { root_dir, patterns, globs, locations }
+  }
+
+  pub(crate) fn includes(&self, file_path: &Path) -> bool {
+    if self.patterns.is_empty() && self.locations.is_empty() {
+      return true;
+    }
+
+    let relative_path =
+      file_path.strip_prefix(self.root_dir).unwrap_or(file_path);
+    let relative_path = relative_path.to_string_lossy();
+
+    self.patterns.iter().any(|pattern| {
+      let pattern = pattern.trim_start_matches("./");
+      relative_path.contains(pattern)
+    }) || self.globs.iter().any(|glob| glob.is_match(file_path))
+      || self
+        .locations
+        .iter()
+        .any(|location| location.matches_file(file_path, self.root_dir))
+  }
+}
+
+impl TaskFilter {
+  pub fn new(
+    only_tasks: Option<TaskSelection>,
+    name_pattern: Option<Regex>,
+  ) -> Self {
+    TaskFilter { only_tasks, name_pattern, located_tasks: None }
+  }
+
+  // Resolves every location to the closest task or suite registered
+  // at or above its line. A suite selects all of its tasks.
+  pub fn with_locations(
+    mut self,
+    locations: &[LocationFilter],
+    file_map: &CollectorFileMap,
+    root_dir: &Path,
+  ) -> Self {
+    if locations.is_empty() {
+      return self;
+    }
+
+    let mut located_tasks = TaskSelection::new();
+
+    for (file_path, file) in file_map {
+      let file_locations = locations
+        .iter()
+        .filter(|location| location.matches_file(file_path, root_dir))
+        .collect::<Vec<_>>();
+
+      if file_locations.is_empty() {
+        continue;
+      }
+
+      let selected = located_tasks
+        .entry(file_path.clone())
+        .or_insert_with(HashSet::new);
+
+      for location in file_locations {
+        let mut closest: Option<(u32, Vec<String>)> = None;
+
+        for node in &file.nodes {
+          let node = node.lock().unwrap();
+          let mut candidates = Vec::new();
+
+          if let CollectorIdentifier::Custom(_) = node.identifier {
+            if let Some(node_location) = &node.location {
+              let task_paths = node
+                .tasks
+                .iter()
+                .map(|task| node.task_path(&task.lock().unwrap()))
+                .collect();
+
+              candidates.push((node_location.line, task_paths));
+            }
+          }
+
+          for task in &node.tasks {
+            let task = task.lock().unwrap();
+
+            if let Some(task_location) = &task.location {
+              let task_path = node.task_path(&task);
+              candidates.push((task_location.line, vec![task_path]));
+            }
+          }
+
+          for (line, task_paths) in candidates {
+            let is_closer = line <= location.line
+              && closest.as_ref().map_or(true, |(c, _)| line >= *c);
+
+            if is_closer {
+              closest = Some((line, task_paths));
+            }
+          }
+        }
+
+        if let Some((_, task_paths)) = closest {
+          selected.extend(task_paths);
+        }
+      }
+    }
+
+    self.located_tasks = Some(located_tasks);
+    self
+  }
+
+  pub fn includes(&self, file_path: &Path, task_path: &str) -> bool {
+    let matches_name = self
+      .name_pattern
+      .as_ref()
+      .map_or(true, |pattern| pattern.is_match(task_path));
+
+    matches_name
+      && Self::selection_includes(&self.only_tasks, file_path, task_path)
+      && Self::selection_includes(
+        &self.located_tasks,
+        file_path,
+        task_path,
+      )
+  }
+
+  fn selection_includes(
+    selection: &Option<TaskSelection>,
+    file_path: &Path,
+    task_path: &str,
+  ) -> bool {
+    let Some(selection) = selection else {
+      return true;
+    };
+
+    selection
+      .get(file_path)
+      .map_or(true, |tasks| tasks.contains(task_path))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::{Path, PathBuf};
+
+  use hashbrown::HashSet;
+  use regex::Regex;
+
+  use crate::runner::filter::{FileFilter, LocationFilter, TaskFilter};
+  use crate::runner::store::TaskSelection;
+
+  #[test]
+  fn test_location_filter() {
+    let root_dir = Path::new("/project");
+    let location = LocationFilter::parse("./a.test.ts:10").unwrap();
+    assert_eq!(
+      (location.file.as_str(), location.line),
+      ("./a.test.ts", 10)
+    );
+
+    assert!(LocationFilter::parse("a.test.ts").is_none());
+    assert!(LocationFilter::parse("a.test.ts:line").is_none());
+
+    let matches =
+      |path: &str| location.matches_file(&root_dir.join(path), root_dir);
+    assert!(matches("a.test.ts"));
+    assert!(matches("tests/a.test.ts"));
+    assert!(!matches("ba.test.ts"));
+    assert!(!matches("a.test.tsx"));
+
+    let nested = LocationFilter::parse("tests/a.test.ts:3").unwrap();
+    let matches =
+      |path: &str| nested.matches_file(&root_dir.join(path), root_dir);
+    assert!(matches("tests/a.test.ts"));
+    assert!(!matches("a.test.ts"));
+    assert!(!matches("other_tests/a.test.ts"));
+  }
+
+  #[test]
+  fn test_file_filter() {
+    let root_dir = Path::new("/project");
+    let file = |path: &str| root_dir.join(path);
+
+    let no_filters = FileFilter::new(&[], &[], root_dir);
+    assert!(no_filters.includes(&file("tests/a.test.ts")));
+
+    let patterns = ["math".to_string(), "./utils/*.test.ts".to_string()];
+    let filter = FileFilter::new(&patterns, &[], root_dir);
+    assert!(filter.includes(&file("tests/math.test.ts")));
+    assert!(filter.includes(&file("utils/fs.test.ts")));
+    assert!(!filter.includes(&file("tests/fs.test.ts")));
+
+    let locations = [LocationFilter::parse("a.test.ts:10").unwrap()];
+    let filter = FileFilter::new(&[], &locations, root_dir);
+    assert!(filter.includes(&file("tests/a.test.ts")));
+    assert!(!filter.includes(&file("tests/ba.test.ts")));
+  }
+
+  #[test]
+  fn test_task_filter() {
+    let file = PathBuf::from("/project/math.test.ts");
+    let other_file = PathBuf::from("/project/other.test.ts");
+
+    let no_filters = TaskFilter::new(None, None);
+    assert!(no_filters.includes(&file, "math > sum"));
+
+    let pattern = Regex::new("^math > ").unwrap();
+    let filter = TaskFilter::new(None, Some(pattern));
+    assert!(filter.includes(&file, "math > sum"));
+    assert!(!filter.includes(&file, "sum"));
+
+    // Files missing from the selection run all of their tasks.
+    let selected = HashSet::from(["math > int > sum".to_string()]);
+    let only_tasks = TaskSelection::from([(file.clone(), selected)]);
+    let filter = TaskFilter::new(Some(only_tasks), None);
+    assert!(filter.includes(&file, "math > int > sum"));
+    assert!(!filter.includes(&file, "math > float > sum"));
+    assert!(filter.includes(&other_file, "math > float > sum"));
+  }
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use deno_core::v8;
+use serde::{Deserialize, Serialize};
+
+use crate::runtime::{KurtexRuntime, KurtexRuntimeOptions};
+use crate::AnyResult;
+
+// Values passed from `globalSetup` modules to test files through
+// `provide` and `inject`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProvidedContext(BTreeMap<String, serde_json::Value>);
+
+impl ProvidedContext {
+  pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+    self.0.get(key)
+  }
+}
+
+// Argument of every `setup` function.
+const SETUP_CONTEXT_SCRIPT: &str = r#"(() => {
+  const provided = {}
+
+  return {
+    provided,
+    provide(key, value) {
+      provided[key] = value
+    }
+  }
+})()"#;
+
+#[derive(Deserialize)]
+struct SetupContext {
+  provided: ProvidedContext,
+}
+
+// Marks errors of a `globalSetup` module that failed to load or
+// threw, the run never started.
+#[derive(Debug)]
+pub struct GlobalSetupError;
+
+impl std::fmt::Display for GlobalSetupError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("Global setup failed")
+  }
+}
+
+// Runs `globalSetup` modules once per run, in a runtime of their own
+// that outlives the test runtimes until the teardown.
+#[derive(Default)]
+pub struct GlobalSetup {
+  // Only created when there are setup modules to run.
+  runtime: Option<KurtexRuntime>,
+  teardowns: Vec<(PathBuf, v8::Global<v8::Function>)>,
+}
+
+impl GlobalSetup {
+  pub fn new() -> Self {
+    GlobalSetup::default()
+  }
+
+  // Calls the `setup` (or default) export of every module in order,
+  // keeping the teardown it returns or exports as `teardown`.
+  pub async fn setup(
+    &mut self,
+    setup_files: &[PathBuf],
+  ) -> AnyResult<ProvidedContext> {
+    if setup_files.is_empty() {
+      return Ok(ProvidedContext::default());
+    }
+
+    let runtime = self.runtime.get_or_insert_with(|| {
+      KurtexRuntime::new(KurtexRuntimeOptions::default())
+    });
+    let setup_context = runtime
+      .execute_script("<kurtex:global-setup>", SETUP_CONTEXT_SCRIPT)?;
+
+    for setup_file in setup_files {
+      let module_id = runtime
+        .resolve_module(setup_file.display().to_string())
+        .await
+        .with_context(|| {
+          format!("Failed to load global setup {}", setup_file.display())
+        })?;
+
+      let setup_fn =
+        match runtime.exported_function(module_id, "setup")? {
+          Some(setup_fn) => Some(setup_fn),
+          None => runtime.exported_function(module_id, "default")?,
+        };
+      let teardown_fn =
+        runtime.exported_function(module_id, "teardown")?;
+
+      if let Some(setup_fn) = setup_fn {
+        let returned = runtime
+          .call_v8_function_with_args(
+            &setup_fn,
+            &[setup_context.clone()],
+          )
+          .await
+          .with_context(|| {
+            format!("Global setup {} failed", setup_file.display())
+          })?;
+
+        if let Some(teardown) = runtime.as_v8_function(&returned) {
+          self.teardowns.push((setup_file.clone(), teardown));
+        }
+      }
+
+      if let Some(teardown_fn) = teardown_fn {
+        self.teardowns.push((setup_file.clone(), teardown_fn));
+      }
+    }
+
+    let setup_context = runtime
+      .deserialize_v8_value::<SetupContext>(&setup_context)
+      .context("Values passed to `provide` must be serializable")?;
+
+    Ok(setup_context.provided)
+  }
+
+  // Teardowns run in reverse order, all of them even if one fails.
+  pub async fn teardown(&mut self) -> AnyResult {
+    let mut result = Ok(());
+    let Some(runtime) = self.runtime.as_mut() else {
+      return result;
+    };
+
+    while let Some((setup_file, teardown_fn)) = self.teardowns.pop() {
+      let teardown_result =
+        runtime.call_v8_function(&teardown_fn).await.with_context(
+          || format!("Global teardown {} failed", setup_file.display()),
+        );
+
+      result = result.and(teardown_result.map(|_| ()));
+    }
+
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::PathBuf;
+
+  use crate::runner::global_setup::GlobalSetup;
+  use crate::util::fs::kurtex_tmp_dir;
+
+  const SETUP_A: &str = r#"
+const events = (globalThis.events ??= [])
+
+export function setup({ provide }) {
+    events.push('setup a')
+    provide('port', 3000)
+
+    return () => events.push('teardown a')
+}
+"#;
+
+  const SETUP_B: &str = r#"
+const events = (globalThis.events ??= [])
+
+export default function ({ provide }) {
+    events.push('setup b')
+    provide('user', { name: 'kurtex' })
+}
+
+export function teardown() {
+    events.push('teardown b')
+}
+"#;
+
+  const FAILING_SETUP: &str = r#"
+export function setup() {
+    throw new Error('setup error')
+}
+"#;
+
+  fn write_setup_files(
+    name: &str,
+    files: &[(&str, &str)],
+  ) -> Vec<PathBuf> {
+    let root_dir = kurtex_tmp_dir().join("global-setup").join(name);
+    let _ = fs::remove_dir_all(&root_dir);
+    fs::create_dir_all(&root_dir).unwrap();
+
+    files
+      .iter()
+      .map(|(path, source)| {
+        let path = root_dir.join(path);
+        fs::write(&path, source).unwrap();
+        path
+      })
+      .collect()
+  }
+
+  fn events(global_setup: &mut GlobalSetup) -> Vec<String> {
+    let runtime = global_setup.runtime.as_mut().unwrap();
+    let events = runtime
+      .execute_script("<kurtex:test>", "globalThis.events")
+      .unwrap();
+
+    runtime.deserialize_v8_value(&events).unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_setup_provide_and_teardown_order() {
+    let setup_files = write_setup_files(
+      "order",
+      &[("a.ts", SETUP_A), ("b.ts", SETUP_B)],
+    );
+    let mut global_setup = GlobalSetup::new();
+
+    let provided = global_setup.setup(&setup_files).await.unwrap();
+    assert_eq!(provided.get("port"), Some(&serde_json::json!(3000)));
+    assert_eq!(
+      provided.get("user"),
+      Some(&serde_json::json!({ "name": "kurtex" }))
+    );
+
+    global_setup.teardown().await.unwrap();
+    assert_eq!(
+      events(&mut global_setup),
+      ["setup a", "setup b", "teardown b", "teardown a"]
+    );
+  }
+
+  #[tokio::test]
+  async fn test_failing_setup() {
+    let setup_files = write_setup_files(
+      "failing",
+      &[("a.ts", SETUP_A), ("failing.ts", FAILING_SETUP)],
+    );
+    let mut global_setup = GlobalSetup::new();
+
+    let error = global_setup.setup(&setup_files).await.unwrap_err();
+    assert!(format!("{:#}", error).contains("failing.ts"));
+
+    // Modules set up before the failing one are torn down.
+    global_setup.teardown().await.unwrap();
+    assert_eq!(events(&mut global_setup), ["setup a", "teardown a"]);
+  }
+
+  #[tokio::test]
+  async fn test_no_setup_files() {
+    let mut global_setup = GlobalSetup::new();
+
+    global_setup.setup(&[]).await.unwrap();
+    global_setup.teardown().await.unwrap();
+    assert!(global_setup.runtime.is_none());
+  }
+}
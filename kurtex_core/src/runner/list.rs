@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use serde::Serialize;
+
+use crate::runner::collector::{FileCollector, TestRunnerConfig};
+use crate::runner::{create_runtime, EmitRuntimeOptions};
+use crate::{
+  AnyResult, CollectorIdentifier, CollectorLocation, CollectorMode,
+  CollectorNode, CollectorTask,
+};
+
+#[derive(Debug, Serialize)]
+pub struct ListedFile {
+  pub file: PathBuf,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  pub tests: Vec<ListedTask>,
+  pub suites: Vec<ListedSuite>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedSuite {
+  pub name: String,
+  pub mode: CollectorMode,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub location: Option<CollectorLocation>,
+  pub tests: Vec<ListedTask>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListedTask {
+  pub name: String,
+  pub mode: CollectorMode,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub location: Option<CollectorLocation>,
+}
+
+impl ListedFile {
+  pub fn all_tests(&self) -> impl Iterator<Item = &ListedTask> {
+    self
+      .tests
+      .iter()
+      .chain(self.suites.iter().flat_map(|suite| suite.tests.iter()))
+  }
+}
+
+// Collects test files without running them and reports the
+// effective mode of every suite and test.
+pub async fn list_tests(
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+) -> AnyResult<Vec<ListedFile>> {
+  let runtime = create_runtime(emit_opts)?;
+  let collector = FileCollector::new(config, runtime);
+  let ctx = collector.run(Default::default()).await?;
+  let ctx = ctx.borrow();
+
+  let mut listed_files = ctx
+    .file_map
+    .values()
+    .map(|file| {
+      let mut listed_file = ListedFile {
+        file: file.file_path.clone(),
+        error: file.error.as_ref().map(|e| e.to_string()),
+        tests: Vec::new(),
+        suites: Vec::new(),
+      };
+
+      for node in &file.nodes {
+        let node = node.lock().unwrap();
+        let tests = node.tasks.iter().map(|task| {
+          let task = task.lock().unwrap();
+          list_task(&task)
+        });
+
+        match &node.identifier {
+          CollectorIdentifier::File => listed_file.tests.extend(tests),
+          CollectorIdentifier::Custom(name) => {
+            listed_file.suites.push(ListedSuite {
+              name: node.suite_name().unwrap_or_else(|| name.clone()),
+              mode: effective_node_mode(&node),
+              location: node.location.clone(),
+              tests: tests.collect(),
+            })
+          }
+        }
+      }
+
+      listed_file
+    })
+    .collect::<Vec<_>>();
+
+  listed_files.sort_by(|a, b| a.file.cmp(&b.file));
+
+  Ok(listed_files)
+}
+
+fn list_task(task: &CollectorTask) -> ListedTask {
+  ListedTask {
+    name: task.name.clone(),
+    mode: effective_mode(task.declared_mode, task.mode),
+    location: task.location.clone(),
+  }
+}
+
+fn effective_node_mode(node: &CollectorNode) -> CollectorMode {
+  effective_mode(node.declared_mode, node.mode)
+}
+
+// `only` is resolved to `run` during collection, keep it visible
+// so leftover `.only` calls can be spotted.
+fn effective_mode(
+  declared_mode: CollectorMode,
+  mode: CollectorMode,
+) -> CollectorMode {
+  match (declared_mode, mode) {
+    (CollectorMode::Only, CollectorMode::Run) => CollectorMode::Only,
+    (_, mode) => mode,
+  }
+}
@@ -1,9 +1,15 @@
+use std::ops::Deref;
 use std::path::PathBuf;
 use std::rc::Rc;
 
+use anyhow::{anyhow, Context};
+use deno_graph::ModuleGraph;
+use log::debug;
 use rayon::prelude::*;
 use rccell::RcCell;
+use tokio::sync::oneshot;
 
+use crate::config::loader::ConfigLoader;
 use crate::deno::ExtensionLoader;
 use crate::ops::CollectorRegistryExt;
 use crate::reporter::Reporter;
@@ -11,60 +17,318 @@ use crate::runner::collector::{
   FileCollector, FileCollectorOptions, RunnerCollectorContext,
   TestRunnerConfig,
 };
+use crate::runner::durations::DurationStore;
+use crate::runner::global_setup::{
+  GlobalSetup, GlobalSetupError, ProvidedContext,
+};
+use crate::runner::report::RunReport;
 use crate::runner::runner::TestRunner;
+use crate::runner::shard::Shard;
+use crate::runner::store::ResultsStore;
+use crate::runner::summary::RunSummary;
 use crate::runtime::{KurtexRuntime, KurtexRuntimeOptions};
+use crate::util::git::{self, ChangedFilesScope};
+use crate::watcher::resolver::WatcherResolver;
 use crate::{watcher, AnyResult};
 
+pub mod bisect;
 pub mod collector;
+pub mod durations;
+pub mod filter;
+pub mod global_setup;
+pub mod list;
+pub mod related;
+pub mod report;
 pub mod reporter;
 pub mod runner;
+pub mod shard;
+pub mod store;
+pub mod summary;
+
+// Exit code of a process killed by SIGINT.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
 
 // TODO: extract config from deno.json
 #[derive(Clone)]
 pub struct EmitRuntimeOptions {
   pub runtime_snapshot: &'static [u8],
+  // Read by `inject` in every test runtime.
+  pub provided: ProvidedContext,
 }
 
+// Runs the tests between the global setup and teardown, the
+// teardown also runs when the run fails or is interrupted.
 pub async fn launch(
   config: Rc<TestRunnerConfig>,
   emit_opts: Rc<EmitRuntimeOptions>,
-) -> AnyResult {
-  let runtime = create_runtime(emit_opts);
+) -> AnyResult<RunSummary> {
+  let mut global_setup = GlobalSetup::new();
+
+  let provided = match global_setup.setup(&config.global_setup).await {
+    Ok(provided) => provided,
+    Err(error) => {
+      let teardown_result = global_setup.teardown().await;
+      return merge_errors(Err(error), teardown_result)
+        .context(GlobalSetupError);
+    }
+  };
+
+  let emit_opts = Rc::new(EmitRuntimeOptions {
+    provided,
+    ..emit_opts.deref().clone()
+  });
+
+  // Ctrl-C keeps its default behavior without anything to tear down.
+  let launch_result = if config.global_setup.is_empty() {
+    launch_tests(config, emit_opts).await
+  } else {
+    tokio::select! {
+      launch_result = launch_tests(config, emit_opts) => launch_result,
+      Ok(()) = interrupt_signal() => Err(anyhow!("Test run interrupted.")),
+    }
+  };
+  let teardown_result = global_setup.teardown().await;
+
+  merge_errors(launch_result, teardown_result)
+}
+
+// Keeps both errors when the teardown fails after a failed run.
+fn merge_errors<T>(
+  result: AnyResult<T>,
+  teardown_result: AnyResult,
+) -> AnyResult<T> {
+  match (result, teardown_result) {
+    (Err(error), Err(teardown_error)) => {
+      Err(anyhow!("{:#}\n\n{:#}", error, teardown_error))
+    }
+    (result, teardown_result) => {
+      teardown_result?;
+      result
+    }
+  }
+}
+
+// Resolves on the first Ctrl-C. The signal is awaited on a thread of
+// its own, the runtime may be blocked by a test stuck in synchronous
+// code. A second Ctrl-C, or one after the run, exits right away.
+fn interrupt_signal() -> oneshot::Receiver<()> {
+  let (interrupt_tx, interrupt_rx) = oneshot::channel();
+
+  std::thread::spawn(move || {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+
+    runtime.block_on(async move {
+      if tokio::signal::ctrl_c().await.is_err() {
+        return;
+      }
+
+      if interrupt_tx.send(()).is_ok() {
+        eprintln!("Interrupted, running global teardown.");
+        eprintln!("Press Ctrl-C again to exit right away.");
+        let _ = tokio::signal::ctrl_c().await;
+      }
+
+      std::process::exit(INTERRUPTED_EXIT_CODE);
+    });
+  });
+
+  interrupt_rx
+}
+
+async fn launch_tests(
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+) -> AnyResult<RunSummary> {
+  let collector_opts = match &config.changed {
+    Some(scope) => {
+      let related_tests =
+        resolve_changed_tests(config.clone(), emit_opts.clone(), scope)
+          .await?;
+
+      if related_tests.is_empty() {
+        println!("No test files related to changed files found.");
+        return Ok(RunSummary::default());
+      }
+
+      FileCollectorOptions {
+        existing_paths: Some(related_tests),
+        ..Default::default()
+      }
+    }
+    None => Default::default(),
+  };
 
+  let collector_opts = match config.shard {
+    Some(shard) => {
+      let test_files = match &collector_opts.existing_paths {
+        Some(paths) => paths.clone(),
+        None => FileCollector::collect_test_files(&config),
+      };
+      let has_test_files = !test_files.is_empty();
+      let sharded_files =
+        select_shard_files(&config, shard, test_files)?;
+
+      if sharded_files.is_empty() {
+        println!("No test files in shard {}.", shard);
+        return Ok(RunSummary {
+          empty_shard: has_test_files,
+          ..Default::default()
+        });
+      }
+
+      FileCollectorOptions {
+        existing_paths: Some(sharded_files),
+        ..collector_opts
+      }
+    }
+    None => collector_opts,
+  };
+
+  let runtime = create_runtime(emit_opts.clone())?;
   let (runtime, ctx) =
-    launch_runner(runtime, config.clone(), None).await?;
+    launch_runner(runtime, config.clone(), collector_opts).await?;
   let module_graph = runtime.borrow_mut().build_graph().await;
 
-  let context = ctx.borrow_mut();
-  context.reporter.report_finished(&context);
+  report_run(&ctx.borrow(), &config)?;
+
+  if let Some(durations_path) = &config.durations_path {
+    let mut durations = DurationStore::load(durations_path)?;
+    durations.record(&ctx.borrow().file_durations, &config.root_dir);
+    durations.save(durations_path)?;
+  }
+
+  let summary = RunSummary::from_context(&ctx.borrow());
 
   if (config.watch) {
-    context.reporter.watcher_started(&context);
+    let mut results = ResultsStore::new();
+    results.update(&ctx.borrow(), None);
+    ctx.borrow().reporter.watcher_started(&ctx.borrow());
 
     fn restart_runner(
-      changed_files: Vec<PathBuf>,
-      runtime: RcCell<KurtexRuntime>,
-      config: Rc<TestRunnerConfig>,
+      collector_opts: FileCollectorOptions,
+      session: &RunnerSession,
     ) {
+      let runtime = session.runtime.clone();
+      let config = session.config.clone();
+      let results = session.results.clone();
+
       deno_core::unsync::spawn(async move {
+        let only_tasks = collector_opts.only_tasks.clone();
         let launch_result =
-          launch_runner(runtime, config, Some(changed_files)).await;
+          launch_runner(runtime, config.clone(), collector_opts).await;
 
         if let Ok((_, ctx)) = launch_result {
-          let context = ctx.borrow_mut();
+          let context = ctx.borrow();
+          let mut results = results.borrow_mut();
+          results.update(&context, only_tasks.as_ref());
+
+          if let Err(err) = report_run(&context, &config) {
+            eprintln!("Watcher: failed to report the run {:?}", err);
+          }
+
+          context.reporter.report_summary(&results);
           context.reporter.watcher_started(&context);
         }
       });
     };
 
-    watcher::start_watcher(
-      Box::new(restart_runner),
-      module_graph,
+    let session = RunnerSession {
       config,
       runtime,
-      &context,
-    )
-    .await?;
+      context: ctx,
+      module_graph,
+      results: RcCell::new(results),
+    };
+
+    watcher::start_watcher(Box::new(restart_runner), session, emit_opts)
+      .await?;
+  }
+
+  Ok(summary)
+}
+
+pub struct RunnerSession {
+  pub config: Rc<TestRunnerConfig>,
+  pub runtime: RcCell<KurtexRuntime>,
+  pub context: RcCell<RunnerCollectorContext>,
+  pub module_graph: Rc<ModuleGraph>,
+  // Cumulative results of every run in the watch session.
+  pub results: RcCell<ResultsStore>,
+}
+
+// Loads the config file again, merged onto the command line options.
+pub async fn reload_config(
+  config: &TestRunnerConfig,
+) -> AnyResult<Rc<TestRunnerConfig>> {
+  let config_loader =
+    ConfigLoader::new(config.config_path.display().to_string());
+  let (config_file, config_dependencies) =
+    config_loader.load_with_dependencies().await?;
+
+  let mut config = config.without_config_file();
+  config.adjust_config_file(config_file);
+  config.config_dependencies = config_dependencies;
+
+  Ok(Rc::new(config))
+}
+
+// Replaces `session` with one that reruns every test file with the
+// reloaded `config` in a fresh runtime.
+pub async fn reload_runner(
+  session: RunnerSession,
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+) -> AnyResult<RunnerSession> {
+  // V8 isolates have to be dropped in reverse creation order, the
+  // old runtime goes before the new one is created. The context
+  // holds handles into it, so it goes first.
+  let RunnerSession { runtime, context, .. } = session;
+  drop(context);
+  drop(runtime);
+
+  start_session(config, emit_opts).await
+}
+
+// Runs every test file in a new runtime and keeps it for watch mode.
+pub async fn start_session(
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+) -> AnyResult<RunnerSession> {
+  let runtime = create_runtime(emit_opts)?;
+  let (runtime, ctx) =
+    launch_runner(runtime, config.clone(), Default::default()).await?;
+  let module_graph = runtime.borrow_mut().build_graph().await;
+  let mut results = ResultsStore::new();
+
+  {
+    let context = ctx.borrow();
+    results.update(&context, None);
+
+    report_run(&context, &config)?;
+    context.reporter.watcher_started(&context);
+  }
+
+  Ok(RunnerSession {
+    config,
+    runtime,
+    context: ctx,
+    module_graph,
+    results: RcCell::new(results),
+  })
+}
+
+// Hands the finished run to every configured reporter.
+fn report_run(
+  ctx: &RunnerCollectorContext,
+  config: &TestRunnerConfig,
+) -> AnyResult {
+  let report = RunReport::from_context(ctx, ctx.reporter.elapsed());
+
+  for reporter in config.reporters() {
+    reporter.report(&ctx.reporter, &report, config)?;
   }
 
   Ok(())
@@ -73,12 +337,11 @@ pub async fn launch(
 async fn launch_runner(
   runtime: RcCell<KurtexRuntime>,
   config: Rc<TestRunnerConfig>,
-  existing_paths: Option<Vec<PathBuf>>,
+  collector_opts: FileCollectorOptions,
 ) -> AnyResult<(RcCell<KurtexRuntime>, RcCell<RunnerCollectorContext>)> {
   let file_collector =
     FileCollector::new(config.clone(), runtime.clone());
-  let collector_ctx =
-    file_collector.run(FileCollectorOptions { existing_paths }).await?;
+  let collector_ctx = file_collector.run(collector_opts).await?;
 
   let mut test_runner = TestRunner::new(
     collector_ctx.clone(),
@@ -91,17 +354,113 @@ async fn launch_runner(
   Ok((runtime, collector_ctx))
 }
 
+// Loads every test file into a separate runtime to build
+// the module graph, without running any tests. Also returns the
+// test files that failed to load, their imports are unknown.
+pub async fn build_module_graph(
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+) -> AnyResult<(Rc<ModuleGraph>, Vec<PathBuf>)> {
+  let runtime = create_runtime(emit_opts)?;
+  let mut runtime = runtime.borrow_mut();
+  let mut failed_files = Vec::new();
+
+  for test_file in FileCollector::collect_test_files(&config) {
+    let load_result =
+      runtime.resolve_test_module(test_file.display().to_string()).await;
+
+    if let Err(error) = load_result {
+      debug!("Failed to load {}: {:#}", test_file.display(), error);
+      failed_files.push(test_file);
+    }
+  }
+
+  // Setup files are roots as well, so changes to them and to their
+  // imports can be traced, see `resolve_related_tests`.
+  for setup_file in config.setup_files.iter() {
+    let load_result = runtime
+      .resolve_test_module(setup_file.display().to_string())
+      .await;
+
+    if let Err(error) = load_result {
+      debug!("Failed to load {}: {:#}", setup_file.display(), error);
+    }
+  }
+
+  Ok((runtime.build_graph().await, failed_files))
+}
+
+// Test files that depend on `file_path`. Setup files run before
+// every test file, so reaching one of them selects all test files.
+pub fn resolve_related_tests(
+  resolver: &mut WatcherResolver,
+  config: &TestRunnerConfig,
+  file_path: PathBuf,
+) -> Option<Vec<PathBuf>> {
+  let test_files = resolver.resolve_dependency_tests(file_path)?;
+
+  if test_files.iter().any(|file| config.setup_files.contains(file)) {
+    return Some(FileCollector::collect_test_files(config));
+  }
+
+  Some(test_files)
+}
+
+async fn resolve_changed_tests(
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+  scope: &ChangedFilesScope,
+) -> AnyResult<Vec<PathBuf>> {
+  let changed_files = git::changed_files(&config.root_dir, scope)?;
+  let (module_graph, failed_files) =
+    build_module_graph(config.clone(), emit_opts).await?;
+  let mut resolver = WatcherResolver::new(module_graph);
+
+  // Test files that failed to load may import a changed file,
+  // running them reports the load error.
+  let mut related_tests = changed_files
+    .into_iter()
+    .filter_map(|file| {
+      resolve_related_tests(&mut resolver, &config, file)
+    })
+    .flatten()
+    .chain(failed_files)
+    .collect::<Vec<_>>();
+
+  related_tests.sort();
+  related_tests.dedup();
+
+  Ok(related_tests)
+}
+
+fn select_shard_files(
+  config: &TestRunnerConfig,
+  shard: Shard,
+  test_files: Vec<PathBuf>,
+) -> AnyResult<Vec<PathBuf>> {
+  let durations = match &config.durations_path {
+    Some(durations_path) => Some(DurationStore::load(durations_path)?),
+    None => None,
+  };
+
+  Ok(shard.select(test_files, &config.root_dir, durations.as_ref()))
+}
+
 fn create_runtime(
   emit_options: Rc<EmitRuntimeOptions>,
-) -> RcCell<KurtexRuntime> {
+) -> AnyResult<RcCell<KurtexRuntime>> {
   let collector_ops_loader: Box<dyn ExtensionLoader> =
     Box::new(CollectorRegistryExt::new());
 
-  let runtime = RcCell::new(KurtexRuntime::new(KurtexRuntimeOptions {
+  let mut runtime = KurtexRuntime::new(KurtexRuntimeOptions {
     loaders: vec![collector_ops_loader],
     snapshot: emit_options.runtime_snapshot,
     is_main: true,
-  }));
+  });
+
+  runtime.mutate_state(|provided: &mut ProvidedContext| {
+    *provided = emit_options.provided.clone();
+  })?;
 
-  runtime
+  Ok(RcCell::new(runtime))
 }
@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::runner::collector::TestRunnerConfig;
+use crate::runner::{
+  build_module_graph, resolve_related_tests, EmitRuntimeOptions,
+};
+use crate::watcher::resolver::WatcherResolver;
+use crate::AnyResult;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedReport {
+  pub related: Vec<RelatedTests>,
+  // Test files that failed to load, they may be related as well.
+  pub failed_files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelatedTests {
+  pub source: PathBuf,
+  pub tests: Vec<RelatedTest>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedTest {
+  pub file: PathBuf,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub import_chain: Option<Vec<PathBuf>>,
+}
+
+// Finds the test files that depend on each of the `sources`,
+// relative paths are resolved against the root directory.
+pub async fn find_related_tests(
+  config: Rc<TestRunnerConfig>,
+  emit_opts: Rc<EmitRuntimeOptions>,
+  sources: Vec<PathBuf>,
+  with_import_chains: bool,
+) -> AnyResult<RelatedReport> {
+  let sources = sources
+    .into_iter()
+    .map(|source| {
+      let source = config.root_dir.join(source);
+
+      source.canonicalize().with_context(|| {
+        format!("Source file {} not found", source.display())
+      })
+    })
+    .collect::<AnyResult<Vec<_>>>()?;
+
+  let (module_graph, failed_files) =
+    build_module_graph(config.clone(), emit_opts).await?;
+  let mut resolver = WatcherResolver::new(module_graph);
+  let mut related = Vec::with_capacity(sources.len());
+
+  for source in sources {
+    let test_files =
+      resolve_related_tests(&mut resolver, &config, source.clone())
+        .unwrap_or_default();
+
+    let tests = test_files
+      .into_iter()
+      .map(|file| {
+        let import_chain = with_import_chains
+          .then(|| resolver.resolve_import_chain(&file, &source))
+          .flatten();
+
+        RelatedTest { file, import_chain }
+      })
+      .collect();
+
+    related.push(RelatedTests { source, tests });
+  }
+
+  Ok(RelatedReport { related, failed_files })
+}
@@ -0,0 +1,449 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::reporter::KurtexDefaultReporter;
+use crate::runner::collector::{
+  RunnerCollectorContext, TestRunnerConfig,
+};
+use crate::runner::durations::relative_key;
+use crate::{
+  AnyResult, CollectorLocation, CollectorMode, CollectorStatus,
+  ReporterKind,
+};
+
+// Bumped whenever the blob layout changes, blobs of another
+// version are rejected by `merge-reports`.
+pub const REPORT_VERSION: u32 = 1;
+
+pub const BLOB_REPORTS_DIR: &str = ".kurtex-reports";
+
+// Serializable result of a finished run.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+  pub version: u32,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub shard: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub seed: Option<u64>,
+  // Wall time in milliseconds.
+  pub duration: f64,
+  pub files: Vec<FileReport>,
+  // Tests that both passed and failed across repeated runs.
+  #[serde(default)]
+  pub flaky: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReport {
+  pub file: PathBuf,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub duration: Option<f64>,
+  pub tasks: Vec<TaskReport>,
+  // Errors thrown by suite hooks, outside of any test.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub suite_errors: Vec<SuiteErrorReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuiteErrorReport {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub suite: Option<String>,
+  pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskReport {
+  pub name: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub suite: Option<String>,
+  pub mode: CollectorMode,
+  pub status: TaskStatus,
+  // Zero for tasks that did not run.
+  #[serde(default)]
+  pub attempts: u32,
+  #[serde(default)]
+  pub runs: u32,
+  #[serde(default)]
+  pub passes: u32,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub location: Option<CollectorLocation>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub skip_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+  Pass,
+  Fail,
+  Skip,
+  Todo,
+}
+
+impl From<CollectorStatus> for TaskStatus {
+  fn from(status: CollectorStatus) -> Self {
+    match status {
+      CollectorStatus::Pass => TaskStatus::Pass,
+      CollectorStatus::Fail => TaskStatus::Fail,
+      CollectorStatus::Custom(CollectorMode::Todo) => TaskStatus::Todo,
+      CollectorStatus::Custom(_) => TaskStatus::Skip,
+    }
+  }
+}
+
+impl TaskReport {
+  // Passed, but only after failing at least once.
+  pub fn passed_on_retry(&self) -> bool {
+    self.status == TaskStatus::Pass && self.attempts > 1
+  }
+
+  pub fn is_flaky(&self) -> bool {
+    self.passes > 0 && self.passes < self.runs
+  }
+
+  pub fn full_name(&self) -> String {
+    match &self.suite {
+      Some(suite) => format!("{} > {}", suite, self.name),
+      None => self.name.clone(),
+    }
+  }
+}
+
+impl RunReport {
+  pub fn from_context(
+    ctx: &RunnerCollectorContext,
+    duration: Duration,
+  ) -> Self {
+    let mut files = ctx
+      .files
+      .iter()
+      .map(|file| {
+        let tasks = file
+          .nodes
+          .iter()
+          .flat_map(|node| {
+            let node = node.lock().unwrap();
+            let suite = node.suite_name();
+
+            node
+              .tasks
+              .iter()
+              .map(|task| {
+                let task = task.lock().unwrap();
+
+                TaskReport {
+                  name: task.name.clone(),
+                  suite: suite.clone(),
+                  mode: task.mode,
+                  status: task.status.into(),
+                  attempts: task.attempts,
+                  runs: task.runs,
+                  passes: task.passes,
+                  error: task.error.as_ref().map(|e| format!("{:#}", e)),
+                  location: task.location.clone(),
+                  skip_reason: task.skip_reason.clone(),
+                }
+              })
+              .collect::<Vec<_>>()
+          })
+          .collect();
+
+        let suite_errors = file
+          .nodes
+          .iter()
+          .filter_map(|node| {
+            let node = node.lock().unwrap();
+            let error = node.error.as_ref()?;
+
+            Some(SuiteErrorReport {
+              suite: node.suite_name(),
+              error: format!("{:#}", error),
+            })
+          })
+          .collect();
+
+        FileReport {
+          file: file.file_path.clone(),
+          error: file.error.as_ref().map(|e| e.to_string()),
+          duration: ctx
+            .file_durations
+            .get(&file.file_path)
+            .map(as_millis),
+          tasks,
+          suite_errors,
+        }
+      })
+      .collect::<Vec<_>>();
+
+    files.sort_by(|a, b| a.file.cmp(&b.file));
+
+    RunReport {
+      version: REPORT_VERSION,
+      shard: None,
+      seed: ctx.seed,
+      duration: as_millis(&duration),
+      files,
+      flaky: Vec::new(),
+    }
+    .with_flaky()
+  }
+
+  // Makes file paths relative, so reports from machines with
+  // different checkouts can be merged.
+  pub fn strip_root(mut self, root_dir: &Path) -> Self {
+    for file in self.files.iter_mut() {
+      file.file = PathBuf::from(relative_key(&file.file, root_dir));
+
+      for task in file.tasks.iter_mut() {
+        if let Some(location) = task.location.as_mut() {
+          location.file =
+            PathBuf::from(relative_key(&location.file, root_dir));
+        }
+      }
+    }
+
+    self.with_flaky()
+  }
+
+  fn with_flaky(mut self) -> Self {
+    self.flaky = self
+      .files
+      .iter()
+      .flat_map(|file| {
+        let file_path = file.file.display().to_string();
+
+        file.tasks.iter().filter(|task| task.is_flaky()).map(
+          move |task| format!("{} > {}", file_path, task.full_name()),
+        )
+      })
+      .collect();
+
+    self
+  }
+
+  pub fn tasks(&self) -> impl Iterator<Item = &TaskReport> {
+    self.files.iter().flat_map(|file| file.tasks.iter())
+  }
+
+  // Shards run side by side, the slowest one is the wall time.
+  pub fn merge(reports: Vec<RunReport>) -> Self {
+    let duration =
+      reports.iter().map(|report| report.duration).fold(0.0, f64::max);
+    let seed = reports.iter().find_map(|report| report.seed);
+    let mut files = reports
+      .into_iter()
+      .flat_map(|report| report.files)
+      .collect::<Vec<_>>();
+
+    files.sort_by(|a, b| a.file.cmp(&b.file));
+
+    RunReport {
+      version: REPORT_VERSION,
+      shard: None,
+      seed,
+      duration,
+      files,
+      flaky: Vec::new(),
+    }
+    .with_flaky()
+  }
+}
+
+impl ReporterKind {
+  pub fn report(
+    &self,
+    reporter: &KurtexDefaultReporter,
+    report: &RunReport,
+    config: &TestRunnerConfig,
+  ) -> AnyResult {
+    match self {
+      ReporterKind::Default => reporter.print_report(report),
+      ReporterKind::Json => {
+        let report = report.clone().strip_root(&config.root_dir);
+        println!("{}", serde_json::to_string_pretty(&report)?);
+      }
+      ReporterKind::Blob => {
+        let blob_path = write_blob(report, config)?;
+        println!("Blob report written to {}", blob_path.display());
+      }
+    }
+
+    Ok(())
+  }
+}
+
+fn write_blob(
+  report: &RunReport,
+  config: &TestRunnerConfig,
+) -> AnyResult<PathBuf> {
+  let mut report = report.clone().strip_root(&config.root_dir);
+  let file_name = match config.shard {
+    Some(shard) => format!("blob-{}-{}.json", shard.index, shard.count),
+    None => "blob.json".to_string(),
+  };
+
+  report.shard = config.shard.map(|shard| shard.to_string());
+
+  let reports_dir = config.root_dir.join(BLOB_REPORTS_DIR);
+  let blob_path = reports_dir.join(file_name);
+
+  std::fs::create_dir_all(&reports_dir)?;
+  std::fs::write(&blob_path, serde_json::to_string(&report)?)
+    .with_context(|| {
+      format!("Unable to write {}", blob_path.display())
+    })?;
+
+  Ok(blob_path)
+}
+
+// Loads every blob in `reports_dir` into a single report.
+pub fn merge_reports(reports_dir: &Path) -> AnyResult<RunReport> {
+  let mut blob_paths = std::fs::read_dir(reports_dir)
+    .with_context(|| {
+      format!("Unable to read {}", reports_dir.display())
+    })?
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+    .collect::<Vec<_>>();
+
+  blob_paths.sort();
+
+  if blob_paths.is_empty() {
+    bail!("No blob reports found in {}", reports_dir.display());
+  }
+
+  let reports = blob_paths
+    .iter()
+    .map(|blob_path| {
+      let content = std::fs::read_to_string(blob_path)?;
+      let report: RunReport = serde_json::from_str(&content)
+        .with_context(|| {
+          format!("Invalid blob {}", blob_path.display())
+        })?;
+
+      if report.version != REPORT_VERSION {
+        bail!(
+          "Blob {} has version {}, expected {}",
+          blob_path.display(),
+          report.version,
+          REPORT_VERSION
+        );
+      }
+
+      Ok(report)
+    })
+    .collect::<AnyResult<Vec<_>>>()?;
+
+  Ok(RunReport::merge(reports))
+}
+
+fn as_millis(duration: &Duration) -> f64 {
+  duration.as_micros() as f64 / 1_000.0
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::PathBuf;
+
+  use crate::runner::collector::TestRunnerConfig;
+  use crate::runner::report::{
+    merge_reports, write_blob, FileReport, RunReport, TaskReport,
+    TaskStatus, BLOB_REPORTS_DIR, REPORT_VERSION,
+  };
+  use crate::runner::shard::Shard;
+  use crate::util::fs::kurtex_tmp_dir;
+  use crate::CollectorMode;
+
+  fn shard_report(
+    config: &TestRunnerConfig,
+    file: &str,
+    duration: f64,
+  ) -> RunReport {
+    let task = TaskReport {
+      name: "passes".to_string(),
+      suite: None,
+      mode: CollectorMode::Run,
+      status: TaskStatus::Pass,
+      attempts: 1,
+      runs: 1,
+      passes: 1,
+      error: None,
+      location: None,
+      skip_reason: None,
+    };
+
+    RunReport {
+      version: REPORT_VERSION,
+      duration,
+      files: vec![FileReport {
+        file: config.root_dir.join(file),
+        error: None,
+        duration: Some(duration),
+        tasks: vec![task],
+        suite_errors: Vec::new(),
+      }],
+      ..Default::default()
+    }
+  }
+
+  fn project_config(name: &str) -> TestRunnerConfig {
+    let root_dir = kurtex_tmp_dir().join("reports").join(name);
+    let _ = fs::remove_dir_all(&root_dir);
+    fs::create_dir_all(&root_dir).unwrap();
+
+    TestRunnerConfig { root_dir, ..Default::default() }
+  }
+
+  #[test]
+  fn test_blob_round_trip() {
+    let mut config = project_config("round-trip");
+
+    for (index, file, duration) in
+      [(2, "b.test.ts", 20.0), (1, "a.test.ts", 10.0)]
+    {
+      config.shard = Some(Shard { index, count: 2 });
+      let report = shard_report(&config, file, duration);
+      write_blob(&report, &config).unwrap();
+    }
+
+    let merged =
+      merge_reports(&config.root_dir.join(BLOB_REPORTS_DIR)).unwrap();
+    let files = merged
+      .files
+      .iter()
+      .map(|file| file.file.clone())
+      .collect::<Vec<_>>();
+
+    // Paths are relative to the root and sorted across shards.
+    assert_eq!(
+      files,
+      [PathBuf::from("a.test.ts"), PathBuf::from("b.test.ts")]
+    );
+    assert_eq!(merged.tasks().count(), 2);
+    assert_eq!(merged.duration, 20.0);
+    assert_eq!(merged.shard, None);
+  }
+
+  #[test]
+  fn test_blob_version_mismatch() {
+    let config = project_config("version");
+    let report = RunReport {
+      version: REPORT_VERSION + 1,
+      ..shard_report(&config, "a.test.ts", 10.0)
+    };
+    write_blob(&report, &config).unwrap();
+
+    let error = merge_reports(&config.root_dir.join(BLOB_REPORTS_DIR))
+      .unwrap_err();
+
+    assert!(error.to_string().contains("has version"));
+  }
+}
@@ -4,11 +4,16 @@ use std::{env, time};
 
 use anyhow::{anyhow, bail};
 use log::debug;
-use nu_ansi_term::Color::{Blue, LightBlue, LightGray, LightGreen, LightYellow, Red, White};
+use nu_ansi_term::Color::{
+  Blue, LightBlue, LightGray, LightGreen, LightYellow, Red, White,
+};
 use nu_ansi_term::{Color, Style};
 use rccell::RcCell;
 
 use crate::runner::collector::RunnerCollectorContext;
+use crate::runner::report::{FileReport, RunReport, TaskStatus};
+use crate::runner::runner::BAIL_REASON;
+use crate::runner::store::ResultsStore;
 use crate::{
   CollectorFile, CollectorMode, CollectorNode, CollectorStatus,
   CollectorTask,
@@ -41,6 +46,14 @@ pub trait Reporter {
   fn end_task(&self, task: Arc<Mutex<CollectorTask>>) {}
   fn watcher_started(&self, ctx: &RunnerCollectorContext) {}
   fn watcher_rerun(&self, files: &Vec<PathBuf>, file: PathBuf) {}
+  fn watcher_config_reload(&self, file: PathBuf) {}
+  fn watcher_manual_rerun(
+    &self,
+    files: &Vec<PathBuf>,
+    failed_only: bool,
+  ) {
+  }
+  fn report_summary(&self, results: &ResultsStore) {}
 }
 
 impl KurtexDefaultReporter {
@@ -49,100 +62,132 @@ impl KurtexDefaultReporter {
 
     KurtexDefaultReporter { start_time }
   }
-}
-
-impl Reporter for KurtexDefaultReporter {
-  fn report_collected(&mut self) {
-    self.start_time = time::Instant::now();
 
-    debug!("Reporter: collected test files.");
+  pub fn elapsed(&self) -> time::Duration {
+    self.start_time.elapsed()
   }
 
-  fn report_finished(&self, ctx: &RunnerCollectorContext) {
-    let end_time = self.start_time.elapsed();
-    let milliseconds = end_time.as_micros() as f64 / 1_000.0;
-
-    debug!("Reporter: finished with {}.", end_time.as_millis());
+  pub fn print_report(&self, report: &RunReport) {
     println!();
 
-    let mut failed_files = ctx
+    let failed_files = report
       .files
       .iter()
       .filter(|file| file.error.is_some())
-      .collect::<Vec<&Arc<CollectorFile>>>();
-
-    let mut failed = task_vec![];
-    let mut passed = task_vec![];
-    let mut runnable = task_vec![];
-    let mut skipped = task_vec![];
-    let mut todo = task_vec![];
-
-    for task_rc in ctx.tasks.iter() {
-      let task = task_rc.lock().unwrap();
-      let is_runnable = matches!(
-        task.status,
-        CollectorStatus::Pass | CollectorStatus::Fail
-      );
+      .collect::<Vec<&FileReport>>();
 
+    let mut failed = Vec::new();
+    let mut passed = Vec::new();
+    let mut expected_failures = Vec::new();
+    let mut passed_on_retry = Vec::new();
+    let mut runnable = Vec::new();
+    let mut skipped = Vec::new();
+    let mut todo = Vec::new();
+
+    for task in report.tasks() {
       match task.status {
-        CollectorStatus::Fail => failed.push(task_rc.clone()),
-        CollectorStatus::Pass => passed.push(task_rc.clone()),
-        CollectorStatus::Custom(CollectorMode::Skip) => {
-          skipped.push(task_rc.clone())
+        TaskStatus::Fail => failed.push(task),
+        TaskStatus::Pass if task.mode == CollectorMode::Fails => {
+          expected_failures.push(task)
         }
-        CollectorStatus::Custom(CollectorMode::Todo) => {
-          todo.push(task_rc.clone())
+        TaskStatus::Pass if task.passed_on_retry() => {
+          passed_on_retry.push(task)
         }
-        _ => {}
+        TaskStatus::Pass => passed.push(task),
+        TaskStatus::Skip => skipped.push(task),
+        TaskStatus::Todo => todo.push(task),
       };
 
-      if is_runnable {
-        runnable.push(task_rc.clone());
+      if matches!(task.status, TaskStatus::Pass | TaskStatus::Fail) {
+        runnable.push(task);
       }
     }
 
-    let print_failed_files = || {
-      let has_failed = !failed_files.is_empty();
+    if !failed_files.is_empty() {
+      println!("Failed to parse {} files", failed_files.len());
 
-      has_failed.then(|| {
-        println!(
-          "{}",
-          format!("Failed to parse {} files", failed_files.len())
-        );
+      failed_files.iter().for_each(|file| {
+        let file_path = file.file.display().to_string();
 
-        failed_files.iter().for_each(|file| {
-          let file_path = file.file_path.display().to_string();
-          let error = file.error.as_ref().map(|e| e.to_string());
+        self.paint(Red, format!("\n {}", file_path));
+        eprintln!("{}", file.error.as_deref().unwrap_or_default());
+        println!();
+      });
+    }
 
-          self.paint(Red, format!("\n {}", file_path));
-          eprintln!("{}", error.unwrap());
-          println!();
-        });
+    let suite_errors = report
+      .files
+      .iter()
+      .flat_map(|file| {
+        file.suite_errors.iter().map(move |suite_error| {
+          let file_path = file.file.display();
+
+          match &suite_error.suite {
+            Some(suite) => {
+              (format!("{} > {}", file_path, suite), suite_error)
+            }
+            None => (file_path.to_string(), suite_error),
+          }
+        })
       })
-    };
+      .collect::<Vec<_>>();
 
-    let print_failed_tasks = || {
-      let has_failed = !failed.is_empty();
+    if !suite_errors.is_empty() {
+      println!("Failed suites ({})", suite_errors.len());
 
-      has_failed.then(|| {
-        println!("{}", format!("Failed tests ({})", failed.len()));
+      suite_errors.iter().for_each(|(suite_name, suite_error)| {
+        let bold_red = Style::new().bold().on(Red);
+        let fail_mark = format!(" {} ", bold_red.paint("FAIL"));
 
-        failed.iter().for_each(|task| {
-          let task = task.lock().unwrap();
-          let error = task.error.as_ref().map(|e| e.to_string());
+        println!("\n {} {}", fail_mark, suite_name);
+        eprintln!("{}", suite_error.error);
+        println!();
+      });
+    }
+
+    if !failed.is_empty() {
+      println!("Failed tests ({})", failed.len());
+
+      failed.iter().for_each(|task| {
+        let bold_red = Style::new().bold().on(Red);
+        let fail_mark = format!(" {} ", bold_red.paint("FAIL"));
+
+        let ratio = (task.runs > 1)
+          .then(|| {
+            format!(" ({} / {} runs passed)", task.passes, task.runs)
+          })
+          .unwrap_or_default();
+
+        println!("\n {} {}{}", fail_mark, task.full_name(), ratio);
+        eprintln!("{}", task.error.as_deref().unwrap_or_default());
+        println!();
+      });
+    }
 
-          let bold_red = Style::new().bold().on(Red);
-          let fail_mark = format!(" {} ", bold_red.paint("FAIL"));
+    let flaky = report
+      .tasks()
+      .filter(|task| task.is_flaky() || task.passed_on_retry())
+      .collect::<Vec<_>>();
 
-          println!("\n {} {}", fail_mark, task.name);
-          eprintln!("{}", error.unwrap());
-          println!();
-        });
-      })
-    };
+    if !flaky.is_empty() {
+      println!("Flaky tests ({})", flaky.len());
 
-    print_failed_files();
-    print_failed_tasks();
+      flaky.iter().for_each(|task| {
+        let flaky_mark = if task.is_flaky() {
+          format!("passed {} / {} runs", task.passes, task.runs)
+        } else {
+          format!("passed on retry {}", task.attempts - 1)
+        };
+
+        println!(
+          " {} {}",
+          task.full_name(),
+          LightYellow.paint(flaky_mark)
+        );
+      });
+
+      println!();
+    }
 
     self.paint_if(
       &failed_files,
@@ -150,15 +195,38 @@ impl Reporter for KurtexDefaultReporter {
       format!("Failed to parse {} files", failed_files.len()),
     );
 
+    self.paint_if(
+      &suite_errors,
+      Red,
+      format!("Failed suites  {}", suite_errors.len()),
+    );
+
     self.paint_if(
       &failed,
       Red,
       format!("Failed {} / {}", failed.len(), runnable.len()),
     );
 
+    // Passing on retry or failing as expected still counts as a pass,
+    // the lines below break those down.
+    let passed_count =
+      passed.len() + passed_on_retry.len() + expected_failures.len();
+
     self.paint(
       LightGreen,
-      format!("Passed {} / {}", passed.len(), runnable.len()),
+      format!("Passed {} / {}", passed_count, runnable.len()),
+    );
+
+    self.paint_if(
+      &passed_on_retry,
+      LightYellow,
+      format!("Passed on retry  {}", passed_on_retry.len()),
+    );
+
+    self.paint_if(
+      &expected_failures,
+      LightBlue,
+      format!("Expected to fail  {}", expected_failures.len()),
     );
 
     self.paint_if(
@@ -169,7 +237,39 @@ impl Reporter for KurtexDefaultReporter {
 
     self.paint_if(&todo, White, format!("Todo  {} ", todo.len()));
 
-    println!("Time {}ms", milliseconds);
+    let bailed = skipped
+      .iter()
+      .filter(|task| task.skip_reason.as_deref() == Some(BAIL_REASON))
+      .count();
+
+    if bailed > 0 {
+      self.paint(
+        Red,
+        format!("Bailed out, {} tests were not run", bailed),
+      );
+    }
+
+    if let Some(seed) = report.seed {
+      println!("Seed {} (--sequence.seed={})", seed, seed);
+    }
+
+    println!("Time {}ms", report.duration);
+  }
+}
+
+impl Reporter for KurtexDefaultReporter {
+  fn report_collected(&mut self) {
+    self.start_time = time::Instant::now();
+
+    debug!("Reporter: collected test files.");
+  }
+
+  fn report_finished(&self, ctx: &RunnerCollectorContext) {
+    let end_time = self.start_time.elapsed();
+
+    debug!("Reporter: finished with {}.", end_time.as_millis());
+
+    self.print_report(&RunReport::from_context(ctx, end_time));
   }
 
   fn watcher_started(&self, ctx: &RunnerCollectorContext) {
@@ -189,6 +289,64 @@ impl Reporter for KurtexDefaultReporter {
       self
         .paint(LightGreen, "\n Watching for file changes...".to_string())
     }
+
+    self.paint(
+      LightGray,
+      " press f to re-run failed tests, a to re-run all tests"
+        .to_string(),
+    );
+  }
+
+  fn watcher_manual_rerun(
+    &self,
+    files: &Vec<PathBuf>,
+    failed_only: bool,
+  ) {
+    let message = match (failed_only, files.is_empty()) {
+      (true, true) => "No failed tests to re-run.".to_string(),
+      (true, false) => {
+        format!("Re-running failed tests in {} files...", files.len())
+      }
+      (false, _) => format!("Re-running {} test files...", files.len()),
+    };
+
+    self.paint(Blue, message);
+  }
+
+  fn report_summary(&self, results: &ResultsStore) {
+    // Stored statuses don't tell retries and expected failures apart,
+    // they are counted as passed like in the run report.
+    let summary = results.summary();
+    let runnable = summary.passed + summary.failed;
+
+    println!("\n Suite ({} files)", summary.files);
+
+    if summary.failed_files > 0 {
+      self.paint(
+        White,
+        format!(" Failed to parse {} files", summary.failed_files),
+      );
+    }
+
+    if summary.failed > 0 {
+      self.paint(
+        Red,
+        format!(" Failed {} / {}", summary.failed, runnable),
+      );
+    }
+
+    self.paint(
+      LightGreen,
+      format!(" Passed {} / {}", summary.passed, runnable),
+    );
+
+    if summary.skipped > 0 {
+      self.paint(LightYellow, format!(" Skipped  {}", summary.skipped));
+    }
+
+    if summary.todo > 0 {
+      self.paint(White, format!(" Todo  {}", summary.todo));
+    }
   }
 
   fn watcher_rerun(&self, files: &Vec<PathBuf>, trigger: PathBuf) {
@@ -200,6 +358,17 @@ impl Reporter for KurtexDefaultReporter {
       format!("File {} changed, re-running tests...", path.display()),
     );
   }
+
+  fn watcher_config_reload(&self, trigger: PathBuf) {
+    let path = trigger
+      .strip_prefix(env::current_dir().unwrap())
+      .unwrap_or(&trigger);
+
+    self.paint(
+      Blue,
+      format!("Config {} changed, reloading...", path.display()),
+    );
+  }
 }
 
 impl Default for KurtexDefaultReporter {
@@ -1,33 +1,52 @@
 use deno_graph::ModuleGraph;
-use std::cell::Ref;
-use std::ops::DerefMut;
+use std::cell::{Cell, Ref};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
+use anyhow::{anyhow, Context};
+use log::debug;
 use rayon::prelude::*;
 use rccell::RcCell;
+use serde_json::json;
 
 use crate::reporter::{KurtexDefaultReporter, Reporter};
 use crate::runner::collector::{
   RunnerCollectorContext, TestRunnerConfig,
 };
+use crate::runner::durations::relative_key;
 use crate::runtime::KurtexRuntime;
+use crate::util::random;
 use crate::{
-  AnyResult, CollectorFile, CollectorMode, CollectorNode,
-  CollectorStatus, CollectorTask, LifetimeHook,
+  AnyResult, CollectorChild, CollectorFile, CollectorIdentifier,
+  CollectorMode, CollectorNode, CollectorStatus, CollectorTask,
+  HookSequence, LifetimeHook, ShuffleOptions, TestCallback,
 };
 
 pub struct TestRunner {
   context: RcCell<RunnerCollectorContext>,
   config: Rc<TestRunnerConfig>,
   runtime: RcCell<KurtexRuntime>,
+  failed_tasks: Cell<u32>,
 }
 
+// Skip reason of tasks left over after bailing out.
+pub const BAIL_REASON: &str = "bail";
+pub const BEFORE_ALL_REASON: &str = "beforeAll hook failed";
+
 trait CallbackInvoker {
+  // Functions returned by the hooks are pushed to `cleanups`.
   async fn invoke_lifetime_hook(
     &self,
     node_rc: &CollectorNode,
     hook_key: LifetimeHook,
+    cleanups: &mut Vec<TestCallback>,
+  ) -> AnyResult;
+  async fn invoke_cleanups(
+    &self,
+    cleanups: Vec<TestCallback>,
+    hook_key: LifetimeHook,
   ) -> AnyResult;
   async fn invoke_task(&self, task_rc: &CollectorTask) -> AnyResult;
 }
@@ -38,16 +57,32 @@ impl TestRunner {
     config: Rc<TestRunnerConfig>,
     runtime: RcCell<KurtexRuntime>,
   ) -> Self {
-    TestRunner { context, config, runtime }
+    TestRunner { context, config, runtime, failed_tasks: Cell::new(0) }
   }
 
   pub async fn run_files(&self) {
     let mut ctx = self.context.borrow_mut();
     ctx.reporter.report_collected();
+    let mut file_durations = Vec::with_capacity(ctx.file_map.len());
+    let files = self.ordered_files(&ctx);
+
+    for file in files.iter() {
+      if self.bailed() {
+        for node in file.nodes.iter() {
+          skip_node(node, BAIL_REASON);
+        }
+        continue;
+      }
 
-    for file in ctx.file_map.values() {
+      let start_time = Instant::now();
       self.run_file(file.clone(), &ctx).await;
+
+      file_durations
+        .push((file.file_path.clone(), start_time.elapsed()));
     }
+
+    ctx.file_durations.extend(file_durations);
+    ctx.bailed = self.bailed();
   }
 
   async fn run_file(
@@ -69,18 +104,33 @@ impl TestRunner {
     }
 
     ctx.reporter.begin_file(file.clone());
-    let mut file_nodes = file.nodes.iter();
 
-    // TODO: parallel
-    while let Some(node) = file_nodes.next() {
-      let node = node.clone();
-      self.run_node(node, &ctx).await;
+    {
+      let mut rt = self.runtime.borrow_mut();
+
+      if let Err(error) =
+        set_mock_file(&mut rt, &file.file_path, false).await
+      {
+        debug!("Failed to scope mocks to the file: {:#}", error);
+      }
+    }
+
+    // Suites run nested in the file level node, in declaration order.
+    for node in root_nodes(&file) {
+      if self.bailed() {
+        skip_node(&node, BAIL_REASON);
+        continue;
+      }
+
+      self.run_node(node, &[], &ctx).await;
     }
   }
 
+  // `ancestors` are the enclosing suites, outermost first.
   async fn run_node(
     &self,
     node_rc: Arc<Mutex<CollectorNode>>,
+    ancestors: &[&CollectorNode],
     ctx: &RunnerCollectorContext,
   ) {
     ctx.reporter.begin_node(node_rc.clone());
@@ -94,22 +144,68 @@ impl TestRunner {
       _ => {}
     }
 
-    let invoked_result: Result<(), anyhow::Error> = try {
+    // Suites without anything to run skip their `*All` hooks.
+    let runs_tasks = has_runnable_tasks(&node);
+    let mut cleanups = Vec::new();
+    let before_all = if runs_tasks {
       self
-        .invoke_lifetime_hook(node.deref_mut(), LifetimeHook::BeforeAll)
-        .await?;
+        .invoke_lifetime_hook(
+          &node,
+          LifetimeHook::BeforeAll,
+          &mut cleanups,
+        )
+        .await
+    } else {
+      Ok(())
+    };
 
-      for task in &node.tasks {
-        self.run_task(task.clone(), &*node, &ctx).await
+    // `*Each` hooks of the enclosing suites wrap nested tasks too.
+    let mut suites = ancestors.to_vec();
+    suites.push(&*node);
+
+    // Suites that already started still run their `afterAll`.
+    for child in self.ordered_children(&node) {
+      let reason = match (&before_all, self.bailed()) {
+        (Err(_), _) => Some(BEFORE_ALL_REASON),
+        (_, true) => Some(BAIL_REASON),
+        _ => None,
+      };
+
+      match child {
+        CollectorChild::Task(task) => {
+          if let Some(reason) = reason {
+            skip_task(&mut task.lock().unwrap(), reason);
+          }
+
+          self.run_task(task, &suites, &ctx).await
+        }
+        CollectorChild::Node(child_node) => {
+          if let Some(reason) = reason {
+            skip_node(&child_node, reason);
+          }
+
+          Box::pin(self.run_node(child_node, &suites, &ctx)).await
+        }
       }
+    }
 
+    let after_all = if runs_tasks {
       self
-        .invoke_lifetime_hook(node.deref_mut(), LifetimeHook::AfterAll)
-        .await?;
+        .invoke_lifetime_hook(
+          &node,
+          LifetimeHook::AfterAll,
+          &mut Vec::new(),
+        )
+        .await
+    } else {
+      Ok(())
     };
+    let cleanup =
+      self.invoke_cleanups(cleanups, LifetimeHook::BeforeAll).await;
 
-    if invoked_result.is_err() {
-      node.status = CollectorStatus::Fail
+    if let Err(error) = before_all.and(after_all).and(cleanup) {
+      node.status = CollectorStatus::Fail;
+      node.error = Some(error);
     }
 
     ctx.reporter.end_node(node_rc.clone());
@@ -118,23 +214,88 @@ impl TestRunner {
   async fn run_task(
     &self,
     task_rc: Arc<Mutex<CollectorTask>>,
-    parent: &CollectorNode,
+    suites: &[&CollectorNode],
     ctx: &RunnerCollectorContext,
   ) {
     let mut task = task_rc.lock().unwrap();
 
     ctx.reporter.begin_task(task_rc.clone());
 
-    if task.mode != CollectorMode::Run {
+    if !task.mode.is_runnable() {
       ctx.reporter.end_task(task_rc.clone());
       return;
     }
 
+    let retry = task.options.retry.or(self.config.retry).unwrap_or(0);
+    let repeats = task.options.repeats.or(self.config.repeats);
+    let repeats = repeats.unwrap_or(1).max(1);
+    let mut last_error = None;
+
+    for _ in 0..repeats {
+      for attempt in 1..=retry + 1 {
+        task.attempts = task.attempts.max(attempt);
+        task.error = None;
+
+        self.run_attempt(&mut task, suites).await;
+
+        if task.status != CollectorStatus::Fail {
+          break;
+        }
+      }
+
+      task.runs += 1;
+
+      match task.status {
+        CollectorStatus::Fail => last_error = task.error.take(),
+        _ => task.passes += 1,
+      }
+    }
+
+    // A single failed run fails the task.
+    if task.passes < task.runs {
+      task.status = CollectorStatus::Fail;
+      task.error = last_error;
+    }
+
+    if task.status == CollectorStatus::Fail {
+      self.failed_tasks.set(self.failed_tasks.get() + 1);
+    }
+
+    ctx.reporter.end_task(task_rc.clone());
+  }
+
+  // Runs the `beforeEach` -> test -> `afterEach` cycle once.
+  // `beforeEach` hooks run from the outermost suite in, `afterEach`
+  // hooks from the innermost suite out.
+  async fn run_attempt(
+    &self,
+    task: &mut CollectorTask,
+    suites: &[&CollectorNode],
+  ) {
+    let mut cleanups = Vec::new();
     let invoked_result: AnyResult = try {
-      self
-        .invoke_lifetime_hook(parent, LifetimeHook::BeforeEach)
-        .await?;
-      self.invoke_task(&*task).await?;
+      self.reset_mocks()?;
+
+      for suite in suites {
+        self
+          .invoke_lifetime_hook(
+            suite,
+            LifetimeHook::BeforeEach,
+            &mut cleanups,
+          )
+          .await?;
+      }
+
+      let task_result = self.invoke_task(&*task).await;
+
+      // `test.fails` passes only when its callback throws.
+      match (task.mode, task_result) {
+        (CollectorMode::Fails, Ok(())) => {
+          Err(anyhow!("Expected test to fail, but it passed."))?
+        }
+        (CollectorMode::Fails, Err(_)) => {}
+        (_, task_result) => task_result?,
+      }
 
       task.status = CollectorStatus::Pass;
     };
@@ -144,15 +305,153 @@ impl TestRunner {
       task.error = invoked_result.err();
     }
 
-    let after_each =
-      self.invoke_lifetime_hook(parent, LifetimeHook::AfterEach).await;
+    let mut after_each = Ok(());
+
+    for suite in suites.iter().rev() {
+      let hook_result = self
+        .invoke_lifetime_hook(
+          suite,
+          LifetimeHook::AfterEach,
+          &mut Vec::new(),
+        )
+        .await;
+
+      after_each = after_each.and(hook_result);
+    }
 
-    if after_each.is_err() {
-      // TODO: handle errors here
+    let cleanup =
+      self.invoke_cleanups(cleanups, LifetimeHook::BeforeEach).await;
+
+    // Keep the test's own error next to the hook error.
+    if let Err(hook_error) = after_each.and(cleanup) {
       task.status = CollectorStatus::Fail;
+      task.error = Some(match task.error.take() {
+        Some(error) => anyhow!("{:#}\n\n{:#}", error, hook_error),
+        None => hook_error,
+      });
     }
+  }
 
-    ctx.reporter.end_task(task_rc.clone());
+  // Files in run order, sorted by path unless shuffled.
+  pub(crate) fn ordered_files(
+    &self,
+    ctx: &RunnerCollectorContext,
+  ) -> Vec<Arc<CollectorFile>> {
+    let mut files = ctx.file_map.values().cloned().collect::<Vec<_>>();
+    files.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+
+    if let Some(seed) = self.shuffle_seed(|shuffle| shuffle.files) {
+      random::shuffle_by_key(&mut files, seed, |file| {
+        relative_key(&file.file_path, &self.config.root_dir)
+      });
+    }
+
+    files
+  }
+
+  // Shuffling reorders tasks among tasks and suites among suites,
+  // each keeps the slots of its kind.
+  fn ordered_children(
+    &self,
+    node: &CollectorNode,
+  ) -> Vec<CollectorChild> {
+    let mut children = node.children.clone();
+
+    if let Some(seed) = self.shuffle_seed(|shuffle| shuffle.tasks) {
+      shuffle_children(&mut children, seed, |child| match child {
+        CollectorChild::Task(task) => {
+          Some(task.lock().unwrap().name.clone())
+        }
+        CollectorChild::Node(_) => None,
+      });
+    }
+
+    if let Some(seed) = self.shuffle_seed(|shuffle| shuffle.suites) {
+      shuffle_children(&mut children, seed, |child| match child {
+        CollectorChild::Node(node) => {
+          Some(format!("{:?}", node.lock().unwrap().identifier))
+        }
+        CollectorChild::Task(_) => None,
+      });
+    }
+
+    children
+  }
+
+  // Runnable tasks as `(file, task path)` in the order they run.
+  pub(crate) fn run_order(
+    &self,
+    ctx: &RunnerCollectorContext,
+  ) -> Vec<(PathBuf, String)> {
+    let mut run_order = Vec::new();
+
+    for file in self.ordered_files(ctx) {
+      for node in root_nodes(&file) {
+        self.push_run_order(&file.file_path, &node, &mut run_order);
+      }
+    }
+
+    run_order
+  }
+
+  fn push_run_order(
+    &self,
+    file_path: &Path,
+    node: &Arc<Mutex<CollectorNode>>,
+    run_order: &mut Vec<(PathBuf, String)>,
+  ) {
+    let node = node.lock().unwrap();
+
+    for child in self.ordered_children(&node) {
+      match child {
+        CollectorChild::Task(task) => {
+          let task = task.lock().unwrap();
+
+          if task.mode.is_runnable() {
+            run_order
+              .push((file_path.to_path_buf(), node.task_path(&task)));
+          }
+        }
+        CollectorChild::Node(child_node) => {
+          self.push_run_order(file_path, &child_node, run_order)
+        }
+      }
+    }
+  }
+
+  // Whether `bail` failed tests have been reached.
+  fn bailed(&self) -> bool {
+    self
+      .config
+      .bail
+      .is_some_and(|bail| self.failed_tasks.get() >= bail.max(1))
+  }
+
+  // Applies `restoreMocks`, `resetMocks` or `clearMocks`, each one
+  // implying the ones after it.
+  fn reset_mocks(&self) -> AnyResult {
+    let script = if self.config.restore_mocks {
+      "globalThis._kurtexInternals.restoreAllMocks()"
+    } else if self.config.reset_mocks {
+      "globalThis._kurtexInternals.resetAllMocks()"
+    } else if self.config.clear_mocks {
+      "globalThis._kurtexInternals.clearAllMocks()"
+    } else {
+      return Ok(());
+    };
+
+    let mut rt = self.runtime.borrow_mut();
+    rt.execute_script("<kurtex:mocks>", script)
+      .context("Failed to reset mocks")?;
+
+    Ok(())
+  }
+
+  fn shuffle_seed<F>(&self, enabled: F) -> Option<u64>
+  where
+    F: FnOnce(&ShuffleOptions) -> bool,
+  {
+    self.config.shuffle_seed().filter(|_| enabled(&self.config.shuffle))
   }
 
   fn report<T, U>(&self, callback: T)
@@ -176,30 +475,180 @@ impl TestRunner {
   }
 }
 
+// Scopes the mocks created and reset from now on to `file`,
+// `reset` drops the mocks of a previous evaluation of the file.
+pub(crate) async fn set_mock_file(
+  rt: &mut KurtexRuntime,
+  file: &Path,
+  reset: bool,
+) -> AnyResult {
+  let set_mock_file = rt.execute_script(
+    "<kurtex:mocks>",
+    "globalThis._kurtexInternals.setMockFile",
+  )?;
+  let set_mock_file = rt
+    .as_v8_function(&set_mock_file)
+    .context("Mock internals are not initialized")?;
+  let args = [
+    rt.serialize_to_v8(&file.display().to_string())?,
+    rt.serialize_to_v8(&reset)?,
+  ];
+
+  rt.call_v8_function_with_args(&set_mock_file, &args).await?;
+
+  Ok(())
+}
+
+// File level nodes, the only ones without a parent suite.
+fn root_nodes(file: &CollectorFile) -> Vec<Arc<Mutex<CollectorNode>>> {
+  file
+    .nodes
+    .iter()
+    .filter(|node| {
+      let node = node.lock().unwrap();
+      matches!(node.identifier, CollectorIdentifier::File)
+    })
+    .cloned()
+    .collect()
+}
+
+// Whether a task of the suite or of a nested suite will run.
+fn has_runnable_tasks(node: &CollectorNode) -> bool {
+  node.children.iter().any(|child| match child {
+    CollectorChild::Task(task) => {
+      task.lock().unwrap().mode.is_runnable()
+    }
+    CollectorChild::Node(node) => {
+      has_runnable_tasks(&node.lock().unwrap())
+    }
+  })
+}
+
+// Shuffles the children `key` returns a key for, the rest stay put.
+fn shuffle_children<F>(
+  children: &mut [CollectorChild],
+  seed: u64,
+  key: F,
+) where
+  F: Fn(&CollectorChild) -> Option<String>,
+{
+  let slots = children
+    .iter()
+    .enumerate()
+    .filter_map(|(index, child)| key(child).map(|_| index))
+    .collect::<Vec<_>>();
+  let mut picked = slots
+    .iter()
+    .map(|&index| children[index].clone())
+    .collect::<Vec<_>>();
+
+  random::shuffle_by_key(&mut picked, seed, |child| key(child).unwrap());
+
+  for (index, child) in slots.into_iter().zip(picked) {
+    children[index] = child;
+  }
+}
+
+// Skips the tasks of the suite and of its nested suites.
+fn skip_node(node: &Arc<Mutex<CollectorNode>>, reason: &str) {
+  let node = node.lock().unwrap();
+
+  for child in node.children.iter() {
+    match child {
+      CollectorChild::Task(task) => {
+        skip_task(&mut task.lock().unwrap(), reason)
+      }
+      CollectorChild::Node(child_node) => skip_node(child_node, reason),
+    }
+  }
+}
+
+fn skip_task(task: &mut CollectorTask, reason: &str) {
+  if task.mode.is_runnable() {
+    task.mode = CollectorMode::Skip;
+    task.status = CollectorStatus::Custom(CollectorMode::Skip);
+    task.skip_reason = Some(reason.to_string());
+  }
+}
+
 impl CallbackInvoker for TestRunner {
   // TODO: arguments
   async fn invoke_lifetime_hook(
     &self,
     node: &CollectorNode,
     hook_key: LifetimeHook,
+    cleanups: &mut Vec<TestCallback>,
   ) -> AnyResult {
     let mut rt = self.runtime.borrow_mut();
-    let hooks_partition = node.hook_manager.get_by(hook_key);
+    let sequence = self.config.hook_sequence();
+    let hooks_partition =
+      node.hook_manager.get_ordered(hook_key.clone(), sequence);
+
+    if sequence == HookSequence::Parallel {
+      let results = rt
+        .call_v8_functions_concurrently(
+          hooks_partition.into_iter().map(|hook_fn| &**hook_fn),
+        )
+        .await;
+      let mut invoked_result = Ok(());
+
+      for result in results {
+        match result {
+          Ok(returned) => cleanups.extend(
+            rt.as_v8_function(&returned).map(TestCallback::from),
+          ),
+          Err(error) => invoked_result = invoked_result.and(Err(error)),
+        }
+      }
+
+      return invoked_result
+        .with_context(|| format!("{} hook failed", hook_key));
+    }
 
     for hook_fn in hooks_partition {
-      if let Err(err) = rt.call_v8_function(hook_fn).await {
-        return Err(err.into());
-      }
+      let returned = rt
+        .call_v8_function(hook_fn)
+        .await
+        .with_context(|| format!("{} hook failed", hook_key))?;
+
+      cleanups
+        .extend(rt.as_v8_function(&returned).map(TestCallback::from));
     }
 
     Ok(())
   }
 
+  // Cleanups run in reverse, all of them even if one fails.
+  async fn invoke_cleanups(
+    &self,
+    cleanups: Vec<TestCallback>,
+    hook_key: LifetimeHook,
+  ) -> AnyResult {
+    let mut rt = self.runtime.borrow_mut();
+    let mut result = Ok(());
+
+    for cleanup_fn in cleanups.iter().rev() {
+      let cleanup_result = rt
+        .call_v8_function(cleanup_fn)
+        .await
+        .with_context(|| format!("{} cleanup failed", hook_key));
+
+      result = result.and(cleanup_result.map(|_| ()));
+    }
+
+    result
+  }
+
+  // Tests get a context object, `test.extend` adds fixtures to it.
   async fn invoke_task(&self, task: &CollectorTask) -> AnyResult {
     let mut rt = self.runtime.borrow_mut();
     let task_fn = &task.callback;
+    let task_context =
+      rt.serialize_to_v8(&json!({ "task": { "name": task.name } }))?;
 
-    if let Err(e) = rt.call_v8_function(&task_fn).await {
+    if let Err(e) =
+      rt.call_v8_function_with_args(&task_fn, &[task_context]).await
+    {
       return Err(e.into());
     }
 
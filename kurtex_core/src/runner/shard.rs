@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+
+use crate::error::AnyError;
+use crate::runner::durations::{relative_key, DurationStore};
+
+// A `--shard=<index>/<count>` slice of the test files, `index`
+// starts at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+  pub index: usize,
+  pub count: usize,
+}
+
+impl FromStr for Shard {
+  type Err = AnyError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (index, count) = s
+      .split_once('/')
+      .ok_or_else(|| anyhow!("Expected <index>/<count>, got '{}'", s))?;
+    let index = index.trim().parse::<usize>()?;
+    let count = count.trim().parse::<usize>()?;
+
+    if count == 0 || index == 0 || index > count {
+      bail!("Shard index must be between 1 and {}, got '{}'", count, s);
+    }
+
+    Ok(Shard { index, count })
+  }
+}
+
+impl std::fmt::Display for Shard {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}/{}", self.index, self.count)
+  }
+}
+
+impl Shard {
+  // Keeps the files assigned to this shard. Every machine has to see
+  // the same file list (and durations) to compute the same split.
+  pub fn select(
+    &self,
+    mut files: Vec<PathBuf>,
+    root_dir: &Path,
+    durations: Option<&DurationStore>,
+  ) -> Vec<PathBuf> {
+    files.sort_by_cached_key(|file| relative_key(file, root_dir));
+    files.dedup();
+
+    match durations.filter(|durations| !durations.is_empty()) {
+      Some(durations) => {
+        self.select_by_duration(files, root_dir, durations)
+      }
+      None => self.select_chunk(files),
+    }
+  }
+
+  // Splits the sorted files into contiguous chunks whose sizes differ
+  // by at most one, so no shard is empty while files >= count.
+  fn select_chunk(&self, files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let start = (self.index - 1) * files.len() / self.count;
+    let end = self.index * files.len() / self.count;
+
+    files.into_iter().skip(start).take(end - start).collect()
+  }
+
+  // Greedily assigns the longest files to the least loaded shard.
+  // Files without a recorded duration count as the average one.
+  fn select_by_duration(
+    &self,
+    files: Vec<PathBuf>,
+    root_dir: &Path,
+    durations: &DurationStore,
+  ) -> Vec<PathBuf> {
+    let known = files
+      .iter()
+      .filter_map(|file| durations.get(file, root_dir))
+      .collect::<Vec<_>>();
+    let average = match known.len() {
+      0 => 1,
+      len => (known.iter().sum::<u64>() / len as u64).max(1),
+    };
+
+    let mut weighted = files
+      .into_iter()
+      .map(|file| {
+        let duration = durations.get(&file, root_dir).unwrap_or(average);
+        (duration, file)
+      })
+      .collect::<Vec<_>>();
+
+    // Stable sort keeps relative path order for equal durations.
+    weighted.sort_by_key(|(duration, _)| std::cmp::Reverse(*duration));
+
+    // Ties go to the shard with fewer files, files recorded with a
+    // zero duration must not pile up in one shard.
+    let mut loads = vec![(0u64, 0usize); self.count];
+    let mut selected = Vec::new();
+
+    for (duration, file) in weighted {
+      let (shard_index, _) = loads
+        .iter()
+        .enumerate()
+        .min_by_key(|(index, load)| (**load, *index))
+        .unwrap();
+
+      loads[shard_index].0 += duration;
+      loads[shard_index].1 += 1;
+
+      if shard_index == self.index - 1 {
+        selected.push(file);
+      }
+    }
+
+    selected.sort();
+    selected
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::{Path, PathBuf};
+  use std::time::Duration;
+
+  use hashbrown::HashMap;
+
+  use crate::runner::durations::DurationStore;
+  use crate::runner::shard::Shard;
+
+  #[test]
+  fn test_shard_select() {
+    let root_dir = Path::new("/project");
+    let files = (0..20)
+      .map(|index| root_dir.join(format!("tests/{index}.test.ts")))
+      .collect::<Vec<_>>();
+
+    assert!("0/2".parse::<Shard>().is_err());
+    assert!("3/2".parse::<Shard>().is_err());
+
+    let mut durations = HashMap::new();
+    durations.insert(files[0].clone(), Duration::from_millis(900));
+    durations.insert(files[1].clone(), Duration::from_millis(100));
+    let mut store = DurationStore::default();
+    store.record(&durations, root_dir);
+
+    for durations in [None, Some(&store)] {
+      let mut all_selected = (1..=3)
+        .flat_map(|index| {
+          let shard = Shard { index, count: 3 };
+          shard.select(files.clone(), root_dir, durations)
+        })
+        .collect::<Vec<PathBuf>>();
+
+      all_selected.sort();
+      let mut expected = files.clone();
+      expected.sort();
+
+      assert_eq!(all_selected, expected);
+    }
+
+    let first_shard = Shard { index: 1, count: 3 };
+    let selected =
+      first_shard.select(files.clone(), root_dir, Some(&store));
+
+    assert!(selected.contains(&files[0]));
+    assert!(!selected.contains(&files[1]));
+  }
+
+  #[test]
+  fn test_shard_select_no_empty_shard() {
+    let root_dir = Path::new("/project");
+
+    for file_count in 1..=12 {
+      let files = (0..file_count)
+        .map(|index| root_dir.join(format!("{index}.test.ts")))
+        .collect::<Vec<_>>();
+
+      // Zero durations must not leave a shard empty either.
+      let durations = files
+        .iter()
+        .map(|file| (file.clone(), Duration::ZERO))
+        .collect::<HashMap<_, _>>();
+      let mut store = DurationStore::default();
+      store.record(&durations, root_dir);
+
+      for count in 1..=file_count {
+        let sizes = (1..=count)
+          .map(|index| {
+            let shard = Shard { index, count };
+            shard.select(files.clone(), root_dir, None).len()
+          })
+          .collect::<Vec<_>>();
+
+        let min = *sizes.iter().min().unwrap();
+        let max = *sizes.iter().max().unwrap();
+
+        assert!(
+          min > 0,
+          "empty shard of {count} for {file_count} files"
+        );
+        assert!(max - min <= 1);
+        assert_eq!(sizes.iter().sum::<usize>(), file_count);
+
+        for index in 1..=count {
+          let shard = Shard { index, count };
+          let selected =
+            shard.select(files.clone(), root_dir, Some(&store));
+          assert!(!selected.is_empty());
+        }
+      }
+    }
+  }
+}
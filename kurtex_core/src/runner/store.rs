@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::runner::collector::RunnerCollectorContext;
+use crate::{CollectorMode, CollectorStatus};
+
+// Tasks allowed to run, keyed by file and suite path.
+// Files missing from the selection run all of their tasks.
+pub type TaskSelection = HashMap<PathBuf, HashSet<String>>;
+
+// Results of every test file that ran during a watch session.
+#[derive(Default, Debug)]
+pub struct ResultsStore {
+  files: HashMap<PathBuf, FileResults>,
+}
+
+#[derive(Default, Debug)]
+struct FileResults {
+  collect_failed: bool,
+  tasks: HashMap<String, CollectorStatus>,
+}
+
+#[derive(Default, Debug, Clone, Copy)]
+pub struct ResultsSummary {
+  pub files: usize,
+  pub failed_files: usize,
+  pub passed: usize,
+  pub failed: usize,
+  pub skipped: usize,
+  pub todo: usize,
+}
+
+impl ResultsStore {
+  pub fn new() -> Self {
+    ResultsStore::default()
+  }
+
+  // Replaces the stored results of the files that just ran.
+  // With `only_tasks`, results of unselected tasks are kept.
+  pub fn update(
+    &mut self,
+    ctx: &RunnerCollectorContext,
+    only_tasks: Option<&TaskSelection>,
+  ) {
+    for file in &ctx.files {
+      let tasks = file.nodes.iter().flat_map(|node| {
+        let node = node.lock().unwrap();
+
+        node
+          .tasks
+          .iter()
+          .map(|task| {
+            let task = task.lock().unwrap();
+            (node.task_path(&task), task.status)
+          })
+          .collect::<Vec<_>>()
+      });
+
+      self.update_file(
+        &file.file_path,
+        file.error.is_some(),
+        tasks,
+        only_tasks.and_then(|s| s.get(&file.file_path)),
+      );
+    }
+  }
+
+  fn update_file(
+    &mut self,
+    file_path: &Path,
+    collect_failed: bool,
+    tasks: impl Iterator<Item = (String, CollectorStatus)>,
+    selected: Option<&HashSet<String>>,
+  ) {
+    let file_results =
+      self.files.entry(file_path.to_path_buf()).or_default();
+
+    if selected.is_none() {
+      file_results.tasks.clear();
+    }
+
+    file_results.collect_failed = collect_failed;
+
+    for (task_path, status) in tasks {
+      if selected.map_or(true, |tasks| tasks.contains(&task_path)) {
+        file_results.tasks.insert(task_path, status);
+      }
+    }
+  }
+
+  pub fn summary(&self) -> ResultsSummary {
+    let mut summary =
+      ResultsSummary { files: self.files.len(), ..Default::default() };
+
+    for file in self.files.values() {
+      if file.collect_failed {
+        summary.failed_files += 1;
+      }
+
+      for status in file.tasks.values() {
+        match status {
+          CollectorStatus::Pass => summary.passed += 1,
+          CollectorStatus::Fail => summary.failed += 1,
+          CollectorStatus::Custom(CollectorMode::Skip) => {
+            summary.skipped += 1
+          }
+          CollectorStatus::Custom(CollectorMode::Todo) => {
+            summary.todo += 1
+          }
+          _ => {}
+        }
+      }
+    }
+
+    summary
+  }
+
+  // Files that failed to collect or contain failed tasks.
+  pub fn failed_files(&self) -> Vec<PathBuf> {
+    self
+      .files
+      .iter()
+      .filter(|(_, file)| {
+        file.collect_failed
+          || file.tasks.values().any(|s| *s == CollectorStatus::Fail)
+      })
+      .map(|(path, _)| path.clone())
+      .collect()
+  }
+
+  // Failed tasks of the files that were collected successfully.
+  pub fn failed_tasks(&self) -> TaskSelection {
+    self
+      .files
+      .iter()
+      .filter(|(_, file)| !file.collect_failed)
+      .filter_map(|(path, file)| {
+        let failed_tasks = file
+          .tasks
+          .iter()
+          .filter(|(_, status)| **status == CollectorStatus::Fail)
+          .map(|(task_path, _)| task_path.clone())
+          .collect::<HashSet<_>>();
+
+        (!failed_tasks.is_empty()).then(|| (path.clone(), failed_tasks))
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use hashbrown::HashSet;
+
+  use crate::runner::store::{ResultsStore, TaskSelection};
+  use crate::{CollectorMode, CollectorStatus};
+
+  #[test]
+  fn test_results_store() {
+    let file = Path::new("/project/math.test.ts");
+    let other_file = Path::new("/project/other.test.ts");
+    let mut store = ResultsStore::new();
+
+    // Same test name in sibling and nested suites.
+    let tasks = [
+      ("math > sum", CollectorStatus::Pass),
+      ("math > int > sum", CollectorStatus::Fail),
+      ("math > float > sum", CollectorStatus::Pass),
+      ("math > skipped", CollectorStatus::Custom(CollectorMode::Skip)),
+    ];
+    let tasks =
+      tasks.into_iter().map(|(path, status)| (path.to_string(), status));
+
+    store.update_file(file, false, tasks, None);
+    store.update_file(other_file, true, std::iter::empty(), None);
+
+    let summary = store.summary();
+    assert_eq!(summary.files, 2);
+    assert_eq!(summary.failed_files, 1);
+    assert_eq!((summary.passed, summary.failed), (2, 1));
+    assert_eq!(summary.skipped, 1);
+
+    let mut failed_files = store.failed_files();
+    failed_files.sort();
+    assert_eq!(failed_files, [file, other_file]);
+
+    let failed_tasks = store.failed_tasks();
+    let expected = HashSet::from(["math > int > sum".to_string()]);
+    assert_eq!(
+      failed_tasks,
+      TaskSelection::from([(file.into(), expected)])
+    );
+
+    // Rerunning the failed tasks keeps the other results.
+    let selected = failed_tasks.get(file);
+    let rerun = [
+      ("math > int > sum".to_string(), CollectorStatus::Pass),
+      ("math > float > sum".to_string(), CollectorStatus::Fail),
+    ];
+    store.update_file(file, false, rerun.into_iter(), selected);
+
+    let summary = store.summary();
+    assert_eq!((summary.passed, summary.failed), (3, 0));
+    assert!(store.failed_tasks().is_empty());
+  }
+}
@@ -0,0 +1,63 @@
+use crate::runner::collector::RunnerCollectorContext;
+use crate::runner::report::TaskStatus;
+use crate::CollectorStatus;
+
+// Outcome of a finished run, mapped to the process exit code.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+  pub passed: usize,
+  pub failed: usize,
+  pub skipped: usize,
+  pub todo: usize,
+  // Test files that failed to load or collect.
+  pub file_errors: usize,
+  // Always zero until snapshot testing lands.
+  pub snapshot_failures: usize,
+  // Errors thrown outside of tests, e.g. in `beforeAll`.
+  pub unhandled_errors: usize,
+  pub bailed: bool,
+  // The run was a shard that got no test files, the other shards ran
+  // them.
+  pub empty_shard: bool,
+}
+
+impl RunSummary {
+  pub fn from_context(ctx: &RunnerCollectorContext) -> Self {
+    let mut summary =
+      RunSummary { bailed: ctx.bailed, ..Default::default() };
+
+    for task in ctx.tasks.iter() {
+      let task = task.lock().unwrap();
+
+      match TaskStatus::from(task.status) {
+        TaskStatus::Pass => summary.passed += 1,
+        TaskStatus::Fail => summary.failed += 1,
+        TaskStatus::Skip => summary.skipped += 1,
+        TaskStatus::Todo => summary.todo += 1,
+      }
+    }
+
+    summary.file_errors =
+      ctx.files.iter().filter(|file| file.error.is_some()).count();
+    summary.unhandled_errors = ctx
+      .nodes
+      .iter()
+      .filter(|node| {
+        node.lock().unwrap().status == CollectorStatus::Fail
+      })
+      .count();
+
+    summary
+  }
+
+  // Collected tests, whether they ran or not.
+  pub fn total(&self) -> usize {
+    self.passed + self.failed + self.skipped + self.todo
+  }
+
+  pub fn has_failures(&self) -> bool {
+    self.failed > 0
+      || self.snapshot_failures > 0
+      || self.unhandled_errors > 0
+  }
+}
@@ -0,0 +1,199 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, Context};
+
+use crate::error::AnyResult;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangedFilesScope {
+  // Staged, unstaged and untracked files.
+  Uncommitted,
+  // Everything that differs from the given revision,
+  // including uncommitted changes.
+  Since(String),
+}
+
+impl ChangedFilesScope {
+  // Value of `--changed`, empty when no revision was given.
+  pub fn from_revision(revision: &str) -> Self {
+    match revision.trim() {
+      "" => ChangedFilesScope::Uncommitted,
+      revision => ChangedFilesScope::Since(revision.to_string()),
+    }
+  }
+}
+
+// Lists files changed in the git repository that contains
+// `root_dir`. Deleted files are kept, tests importing them
+// are affected as well.
+pub fn changed_files(
+  root_dir: &Path,
+  scope: &ChangedFilesScope,
+) -> AnyResult<Vec<PathBuf>> {
+  let repo_root =
+    git_lines(root_dir, &["rev-parse", "--show-toplevel"])?
+      .pop()
+      .map(PathBuf::from)
+      .ok_or_else(|| {
+        anyhow!("Failed to resolve git repository root.")
+      })?;
+
+  let revision = match scope {
+    ChangedFilesScope::Uncommitted if has_commits(root_dir) => {
+      "HEAD".to_string()
+    }
+    // Without commits, every tracked file is new.
+    ChangedFilesScope::Uncommitted => empty_tree(root_dir)?,
+    ChangedFilesScope::Since(revision) => revision.clone(),
+  };
+
+  let mut changed_files =
+    git_lines(root_dir, &["diff", "--name-only", &revision, "--"])?;
+  changed_files.extend(git_lines(
+    root_dir,
+    &["ls-files", "--others", "--exclude-standard", "--full-name"],
+  )?);
+
+  let mut changed_files = changed_files
+    .into_iter()
+    .map(|path| repo_root.join(path))
+    .collect::<Vec<_>>();
+
+  changed_files.sort();
+  changed_files.dedup();
+
+  Ok(changed_files)
+}
+
+fn has_commits(cwd: &Path) -> bool {
+  git_lines(cwd, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok()
+}
+
+// Hash of the empty tree, which depends on the object format.
+fn empty_tree(cwd: &Path) -> AnyResult<String> {
+  // `output` closes stdin, so the hashed object is empty.
+  git_lines(cwd, &["hash-object", "-t", "tree", "--stdin"])?
+    .pop()
+    .ok_or_else(|| anyhow!("Failed to hash the empty git tree."))
+}
+
+fn git_lines(cwd: &Path, args: &[&str]) -> AnyResult<Vec<String>> {
+  let output = Command::new("git")
+    .args(args)
+    .current_dir(cwd)
+    .output()
+    .context("Failed to run git. Is it installed?")?;
+
+  if !output.status.success() {
+    return Err(anyhow!(
+      "git {} failed: {}",
+      args.join(" "),
+      String::from_utf8_lossy(&output.stderr).trim()
+    ));
+  }
+
+  let lines = String::from_utf8(output.stdout)?
+    .lines()
+    .filter(|line| !line.is_empty())
+    .map(ToOwned::to_owned)
+    .collect();
+
+  Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs;
+  use std::path::{Path, PathBuf};
+  use std::process::Command;
+
+  use crate::util::fs::kurtex_tmp_dir;
+  use crate::util::git::{changed_files, ChangedFilesScope};
+
+  fn git(cwd: &Path, args: &[&str]) {
+    let status = Command::new("git")
+      .args(["-c", "user.name=kurtex", "-c", "user.email=kurtex@test"])
+      .args(args)
+      .current_dir(cwd)
+      .output()
+      .unwrap()
+      .status;
+
+    assert!(status.success(), "git {:?} failed", args);
+  }
+
+  fn init_repo(name: &str) -> PathBuf {
+    let repo_dir = kurtex_tmp_dir().join("tests").join(name);
+    let _ = fs::remove_dir_all(&repo_dir);
+    fs::create_dir_all(&repo_dir).unwrap();
+
+    git(&repo_dir, &["init", "-q"]);
+    repo_dir.canonicalize().unwrap()
+  }
+
+  #[test]
+  fn test_changed_files() {
+    let repo_dir = init_repo("git");
+
+    fs::write(repo_dir.join("committed.ts"), "export {}").unwrap();
+    fs::write(repo_dir.join("deleted.ts"), "export {}").unwrap();
+    fs::write(repo_dir.join("unchanged.ts"), "export {}").unwrap();
+    git(&repo_dir, &["add", "."]);
+    git(&repo_dir, &["commit", "-q", "-m", "init"]);
+
+    fs::write(repo_dir.join("committed.ts"), "export const a = 1")
+      .unwrap();
+    fs::remove_file(repo_dir.join("deleted.ts")).unwrap();
+    fs::write(repo_dir.join("untracked.ts"), "export {}").unwrap();
+
+    let changed =
+      changed_files(&repo_dir, &ChangedFilesScope::Uncommitted).unwrap();
+
+    assert_eq!(
+      changed,
+      [
+        repo_dir.join("committed.ts"),
+        repo_dir.join("deleted.ts"),
+        repo_dir.join("untracked.ts")
+      ]
+    );
+
+    fs::remove_dir_all(repo_dir).unwrap();
+  }
+
+  #[test]
+  fn test_changed_files_without_commits() {
+    let repo_dir = init_repo("git-empty");
+
+    fs::write(repo_dir.join("staged.ts"), "export {}").unwrap();
+    fs::write(repo_dir.join("untracked.ts"), "export {}").unwrap();
+    git(&repo_dir, &["add", "staged.ts"]);
+
+    let changed =
+      changed_files(&repo_dir, &ChangedFilesScope::Uncommitted).unwrap();
+
+    assert_eq!(
+      changed,
+      [repo_dir.join("staged.ts"), repo_dir.join("untracked.ts")]
+    );
+
+    fs::remove_dir_all(repo_dir).unwrap();
+  }
+
+  #[test]
+  fn test_scope_from_revision() {
+    assert_eq!(
+      ChangedFilesScope::from_revision(""),
+      ChangedFilesScope::Uncommitted
+    );
+    assert_eq!(
+      ChangedFilesScope::from_revision("main"),
+      ChangedFilesScope::Since("main".to_string())
+    );
+    assert_eq!(
+      ChangedFilesScope::from_revision(" HEAD~2 "),
+      ChangedFilesScope::Since("HEAD~2".to_string())
+    );
+  }
+}
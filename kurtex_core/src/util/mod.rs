@@ -1,3 +1,5 @@
 pub mod fs;
+pub mod git;
 mod macros;
+pub mod random;
 pub mod tokio;
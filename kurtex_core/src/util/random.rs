@@ -0,0 +1,69 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn fnv1a_hash(bytes: &[u8]) -> u64 {
+  const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+  const PRIME: u64 = 0x100000001b3;
+
+  bytes.iter().fold(OFFSET_BASIS, |hash, byte| {
+    (hash ^ *byte as u64).wrapping_mul(PRIME)
+  })
+}
+
+// Spreads close inputs (e.g. consecutive seeds) over the whole range.
+fn mix(mut value: u64) -> u64 {
+  value ^= value >> 30;
+  value = value.wrapping_mul(0xbf58476d1ce4e5b9);
+  value ^= value >> 27;
+  value = value.wrapping_mul(0x94d049bb133111eb);
+  value ^ (value >> 31)
+}
+
+pub fn random_seed() -> u64 {
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_nanos() as u64)
+    .unwrap_or_default();
+
+  mix(nanos ^ std::process::id() as u64) % 1_000_000
+}
+
+// Orders items by a seeded hash of their key. Each item keeps its
+// relative position for a given seed, no matter which other items
+// are present, so a failing order reproduces on a subset of tests.
+pub fn shuffle_by_key<T, F>(items: &mut [T], seed: u64, key: F)
+where
+  F: Fn(&T) -> String,
+{
+  items.sort_by_cached_key(|item| {
+    mix(fnv1a_hash(key(item).as_bytes()) ^ mix(seed))
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::util::random::shuffle_by_key;
+
+  #[test]
+  fn test_shuffle_by_key() {
+    let items = (0..50).map(|i| format!("test {i}")).collect::<Vec<_>>();
+    let shuffle = |mut items: Vec<String>, seed| {
+      shuffle_by_key(&mut items, seed, |item| item.clone());
+      items
+    };
+
+    let first = shuffle(items.clone(), 42);
+
+    assert_eq!(first, shuffle(items.clone(), 42));
+    assert_ne!(first, shuffle(items.clone(), 43));
+    assert_ne!(first, items);
+
+    let subset = items.iter().step_by(3).cloned().collect::<Vec<_>>();
+    let expected = first
+      .iter()
+      .filter(|item| subset.contains(item))
+      .cloned()
+      .collect::<Vec<_>>();
+
+    assert_eq!(shuffle(subset, 42), expected);
+  }
+}
@@ -97,7 +97,7 @@ where
 pub fn run_async<R>(
   f: impl Future<Output = AnyResult<R>>,
   runtime: Option<Runtime>,
-) {
+) -> AnyResult<R> {
   let runtime = runtime.unwrap_or_else(|| {
     tokio::runtime::Builder::new_current_thread()
       .enable_all()
@@ -105,7 +105,7 @@ pub fn run_async<R>(
       .expect("Failed to build a runtime")
   });
 
-  runtime.block_on(f).expect("Failed to run the given task");
+  let result = runtime.block_on(f);
 
   let handle = runtime.spawn(async {
     tokio::task::yield_now().await;
@@ -124,6 +124,8 @@ pub fn run_async<R>(
   drop(runtime);
   drop(tx);
   _ = timeout.join();
+
+  result
 }
 
 pub use concurrently;
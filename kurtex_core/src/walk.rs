@@ -1,4 +1,6 @@
+use crate::error::AnyResult;
 use crate::fs::add_file_extension;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use globwalk;
 use std::path::{Path, PathBuf};
 
@@ -95,6 +97,47 @@ impl<'a> Walk<'a> {
   }
 }
 
+// Matches paths against a set of globs relative to `root_dir`.
+pub struct GlobMatcher {
+  root_dir: PathBuf,
+  glob_set: GlobSet,
+}
+
+impl GlobMatcher {
+  pub fn new<S>(patterns: &[S], root_dir: &Path) -> AnyResult<Self>
+  where
+    S: AsRef<str>,
+  {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+      builder.add(Glob::new(pattern.as_ref())?);
+    }
+
+    Ok(GlobMatcher {
+      root_dir: root_dir.to_path_buf(),
+      glob_set: builder.build()?,
+    })
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.glob_set.is_empty()
+  }
+
+  pub fn is_match(&self, path: &Path) -> bool {
+    let relative_path =
+      path.strip_prefix(&self.root_dir).unwrap_or(path);
+
+    self.glob_set.is_match(relative_path)
+  }
+
+  // `**/node_modules/**` only matches a directory path
+  // with a trailing separator.
+  pub fn is_dir_match(&self, path: &Path) -> bool {
+    self.is_match(&path.join(""))
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use std::path::PathBuf;
@@ -103,7 +146,7 @@ mod tests {
   use tokio::fs::File;
 
   use crate::util::fs::kurtex_tmp_dir;
-  use crate::walk::{Extensions, Walk};
+  use crate::walk::{Extensions, GlobMatcher, Walk};
 
   #[tokio::test]
   #[should_panic]
@@ -134,4 +177,20 @@ mod tests {
 
     fs::remove_dir_all(kurtex_tmp_dir()).await.unwrap();
   }
+
+  #[test]
+  fn test_glob_matcher() {
+    let root_dir = PathBuf::from("/dev/project");
+    let matcher = GlobMatcher::new(
+      &["**/node_modules/**", "fixtures/*.json"],
+      &root_dir,
+    )
+    .unwrap();
+
+    assert!(matcher.is_match(&root_dir.join("fixtures/users.json")));
+    assert!(matcher.is_match(&root_dir.join("node_modules/a/b.ts")));
+    assert!(matcher.is_dir_match(&root_dir.join("node_modules")));
+    assert!(!matcher.is_match(&root_dir.join("src/main.ts")));
+    assert!(!matcher.is_dir_match(&root_dir.join("src")));
+  }
 }
@@ -1,87 +1,295 @@
-use std::future;
-use std::future::Future;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::time::Duration;
 
-use deno_core::futures::{SinkExt, StreamExt};
 use deno_core::futures::channel::mpsc;
-use deno_graph::ModuleGraph;
-use notify::{INotifyWatcher, Watcher};
-use rccell::RcCell;
+use deno_core::futures::{SinkExt, StreamExt};
+use notify::{INotifyWatcher, PollWatcher, Watcher};
+use tokio::io::{AsyncBufReadExt, BufReader};
 
-use crate::AnyResult;
 use crate::reporter::Reporter;
 use crate::runner::collector::{
-  RunnerCollectorContext, TestRunnerConfig,
+  FileCollector, FileCollectorOptions, TestRunnerConfig,
 };
-use crate::runtime::KurtexRuntime;
+use crate::runner::{
+  reload_config, reload_runner, resolve_related_tests,
+  EmitRuntimeOptions, RunnerSession,
+};
+use crate::walk::GlobMatcher;
 use crate::watcher::resolver::WatcherResolver;
 use crate::watcher::watcher::{
-  AsyncWatcherDebouncer, DebouncedEventKind, DebounceEventResult,
-  DEBOUNCER_CHANNEL_BUFFER,
+  AsyncWatcherDebouncer, DebounceEventResult, DebouncedEventKind,
+  DebouncerConfig, DEBOUNCER_CHANNEL_BUFFER,
 };
+use crate::{AnyResult, RerunTrigger, WatchOptions};
 
 pub mod resolver;
 pub mod watcher;
 
-pub type RestartRunnerFn =
-  dyn Fn(Vec<PathBuf>, RcCell<KurtexRuntime>, Rc<TestRunnerConfig>);
+pub type RestartRunnerFn = dyn Fn(FileCollectorOptions, &RunnerSession);
 
 // TODO: improve watcher options (according to graph),
 // custom folder scope selection
 pub async fn start_watcher(
   trigger: Box<RestartRunnerFn>,
-  module_graph: Rc<ModuleGraph>,
-  config: Rc<TestRunnerConfig>,
-  runtime: RcCell<KurtexRuntime>,
-  ctx: &RunnerCollectorContext,
+  session: RunnerSession,
+  emit_opts: Rc<EmitRuntimeOptions>,
 ) -> AnyResult {
-  let path = config.root_dir.as_ref();
-  let (mut watcher, outer_rx) = init_watcher()?;
-  let mut resolver = WatcherResolver::new(module_graph);
-
-  watcher.watch(path);
-
-  outer_rx
-    .for_each(|debounce_result| {
-      match debounce_result {
-        Ok(events) => events.iter().for_each(|ev| {
-          if ev.kind == DebouncedEventKind::Update {
-            let path = ev.path.clone();
-            let changed_files =
-              resolver.resolve_dependency_tests(path.clone());
-
-            if !changed_files.is_empty() {
-              ctx.reporter.watcher_rerun(&changed_files, path);
-              trigger(changed_files, runtime.clone(), config.clone())
-            }
-          }
-        }),
+  let watch_options = session.config.watch_options.clone();
+
+  if watch_options.use_polling {
+    let (watcher, outer_rx) =
+      init_watcher::<PollWatcher>(&watch_options)?;
+    watch_changes(watcher, outer_rx, trigger, session, emit_opts).await
+  } else {
+    let (watcher, outer_rx) =
+      init_watcher::<INotifyWatcher>(&watch_options)?;
+    watch_changes(watcher, outer_rx, trigger, session, emit_opts).await
+  }
+}
+
+async fn watch_changes<T: Watcher>(
+  mut watcher: AsyncWatcherDebouncer<T>,
+  mut outer_rx: mpsc::Receiver<DebounceEventResult>,
+  trigger: Box<RestartRunnerFn>,
+  session: RunnerSession,
+  emit_opts: Rc<EmitRuntimeOptions>,
+) -> AnyResult {
+  let path = session.config.root_dir.clone();
+  let mut resolver = WatcherResolver::new(session.module_graph.clone());
+  let mut matchers = WatchMatchers::new(&session.config)?;
+  let mut session = session;
+
+  watcher.watch_filtered(&path, &matchers.excludes)?;
+  let mut stdin_lines = BufReader::new(tokio::io::stdin()).lines();
+
+  loop {
+    let debounce_result = tokio::select! {
+      debounce_result = outer_rx.next() => match debounce_result {
+        Some(debounce_result) => debounce_result,
+        None => break,
+      },
+      Ok(Some(command)) = stdin_lines.next_line() => {
+        if let Some(collector_opts) = manual_rerun(&command, &session) {
+          trigger(collector_opts, &session);
+        }
+
+        continue;
+      }
+    };
+
+    let events = match debounce_result {
+      Ok(events) => events,
+      Err(err) => {
+        eprintln!("Watcher: error while processing events {:?}", err);
+        watcher.close();
+        continue;
+      }
+    };
+
+    let events = events
+      .into_iter()
+      .filter(|ev| !matchers.excludes.is_match(&ev.path))
+      .collect::<Vec<_>>();
+
+    // A recursive watch already covers new directories.
+    if !matchers.excludes.is_empty() {
+      let created_dirs = events
+        .iter()
+        .filter(|ev| ev.kind == DebouncedEventKind::CreateDir)
+        .filter(|ev| !ev.path.is_symlink())
+        .filter(|ev| !matchers.excludes.is_dir_match(&ev.path));
+
+      for ev in created_dirs {
+        if let Err(err) =
+          watcher.watch_filtered(&ev.path, &matchers.excludes)
+        {
+          eprintln!("Watcher: failed to watch {:?}", err);
+        }
+      }
+    }
+
+    let changed_config = events.iter().find(|ev| {
+      ev.kind == DebouncedEventKind::Update
+        && session.config.config_dependencies.contains(&ev.path)
+    });
+
+    if let Some(ev) = changed_config {
+      let reporter_ctx = session.context.borrow();
+      reporter_ctx.reporter.watcher_config_reload(ev.path.clone());
+      drop(reporter_ctx);
+
+      let config = match reload_config(&session.config).await {
+        Ok(config) => config,
         Err(err) => {
-          eprintln!("Watcher: error while processing events {:?}", err);
-          watcher.close();
+          eprintln!("Watcher: failed to reload config {:?}", err);
+          continue;
         }
+      };
+
+      // Invalid globs keep the previous matchers and watches.
+      let reloaded_matchers = WatchMatchers::new(&config);
+
+      session =
+        reload_runner(session, config, emit_opts.clone()).await?;
+      resolver = WatcherResolver::new(session.module_graph.clone());
+
+      let watch_result: AnyResult = try {
+        let reloaded_matchers = reloaded_matchers?;
+        watcher.unwatch_all();
+        matchers = reloaded_matchers;
+        watcher.watch_filtered(&path, &matchers.excludes)?;
+      };
+
+      if let Err(err) = watch_result {
+        eprintln!("Watcher: failed to apply watch options {:?}", err);
       }
 
-      future::ready(())
-    })
-    .await;
+      continue;
+    }
+
+    for ev in events.iter() {
+      if ev.kind == DebouncedEventKind::Update {
+        let path = ev.path.clone();
+        let changed_files =
+          match matchers.forced_tests(&path, &session.config) {
+            Some(test_files) => test_files,
+            None => resolve_related_tests(
+              &mut resolver,
+              &session.config,
+              path.clone(),
+            )
+            .unwrap_or_default(),
+          };
+
+        if !changed_files.is_empty() {
+          let ctx = session.context.borrow();
+          ctx.reporter.watcher_rerun(&changed_files, path);
+          drop(ctx);
+
+          let collector_opts = FileCollectorOptions {
+            existing_paths: Some(changed_files),
+            ..Default::default()
+          };
+          trigger(collector_opts, &session)
+        }
+      }
+    }
+  }
 
   Ok(())
 }
 
-fn init_watcher() -> AnyResult<(
-  AsyncWatcherDebouncer,
+// Keyboard commands: `a` reruns every test file,
+// `f` reruns only the tests that failed so far.
+fn manual_rerun(
+  command: &str,
+  session: &RunnerSession,
+) -> Option<FileCollectorOptions> {
+  let ctx = session.context.borrow();
+
+  match command.trim() {
+    "a" => {
+      let test_files =
+        FileCollector::collect_test_files(&session.config);
+      ctx.reporter.watcher_manual_rerun(&test_files, false);
+
+      Some(FileCollectorOptions {
+        existing_paths: Some(test_files),
+        ..Default::default()
+      })
+    }
+    "f" => {
+      let results = session.results.borrow();
+      let failed_files = results.failed_files();
+      ctx.reporter.watcher_manual_rerun(&failed_files, true);
+
+      (!failed_files.is_empty()).then(|| FileCollectorOptions {
+        existing_paths: Some(failed_files),
+        only_tasks: Some(results.failed_tasks()),
+      })
+    }
+    _ => None,
+  }
+}
+
+// Path filters built from the watch options of the config file.
+struct WatchMatchers {
+  excludes: GlobMatcher,
+  rerun_triggers: Vec<(GlobMatcher, Option<GlobMatcher>)>,
+}
+
+impl WatchMatchers {
+  fn new(config: &TestRunnerConfig) -> AnyResult<Self> {
+    let root_dir = &config.root_dir;
+    let excludes =
+      GlobMatcher::new(&config.watch_options.exclude, root_dir)?;
+
+    let rerun_triggers = config
+      .force_rerun_triggers
+      .iter()
+      .map(|trigger| {
+        let pattern = GlobMatcher::new(&[trigger.pattern()], root_dir)?;
+        let scope = match trigger {
+          RerunTrigger::Scoped { tests, .. } => {
+            Some(GlobMatcher::new(tests, root_dir)?)
+          }
+          RerunTrigger::Pattern(_) => None,
+        };
+
+        Ok((pattern, scope))
+      })
+      .collect::<AnyResult<Vec<_>>>()?;
+
+    Ok(WatchMatchers { excludes, rerun_triggers })
+  }
+
+  // Test files to rerun when `path` matches a force rerun trigger.
+  fn forced_tests(
+    &self,
+    path: &Path,
+    config: &TestRunnerConfig,
+  ) -> Option<Vec<PathBuf>> {
+    let scopes = self
+      .rerun_triggers
+      .iter()
+      .filter(|(pattern, _)| pattern.is_match(path))
+      .map(|(_, scope)| scope)
+      .collect::<Vec<_>>();
+
+    if scopes.is_empty() {
+      return None;
+    }
+
+    let test_files = FileCollector::collect_test_files(config);
+
+    if scopes.iter().any(|scope| scope.is_none()) {
+      return Some(test_files);
+    }
+
+    let scoped_files = test_files
+      .into_iter()
+      .filter(|file| scopes.iter().flatten().any(|s| s.is_match(file)))
+      .collect();
+
+    Some(scoped_files)
+  }
+}
+
+fn init_watcher<T: Watcher>(
+  options: &WatchOptions,
+) -> AnyResult<(
+  AsyncWatcherDebouncer<T>,
   mpsc::Receiver<DebounceEventResult>,
 )> {
   let (outer_tx, outer_rx) =
     mpsc::channel::<DebounceEventResult>(DEBOUNCER_CHANNEL_BUFFER);
 
-  let mut watcher = AsyncWatcherDebouncer::<INotifyWatcher>::new(
-    Duration::from_millis(1500),
-    outer_tx,
-  );
+  let debouncer_config =
+    DebouncerConfig::new(Duration::from_millis(options.debounce))
+      .with_poll_interval(Duration::from_millis(options.interval));
+  let watcher =
+    AsyncWatcherDebouncer::<T>::with_config(debouncer_config, outer_tx)?;
 
   Ok((watcher, outer_rx))
 }
@@ -1,8 +1,10 @@
-use std::path::PathBuf;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use deno_graph::ModuleSpecifier;
 use deno_graph::{GraphKind, ModuleGraph};
+use hashbrown::HashMap;
 use rayon::prelude::*;
 
 pub struct WatcherResolver {
@@ -27,14 +29,15 @@ impl WatcherResolver {
     }
   }
 
+  // Test files importing `file_path`, `None` unless it is absolute.
   pub fn resolve_dependency_tests(
     &mut self,
     file_path: PathBuf,
-  ) -> Vec<PathBuf> {
+  ) -> Option<Vec<PathBuf>> {
     self.changed_files.clear();
     self.cached_files.clear();
 
-    let specifier = ModuleSpecifier::from_file_path(file_path).unwrap();
+    let specifier = ModuleSpecifier::from_file_path(file_path).ok()?;
     Self::resolve_dependencies(
       &mut self.cached_files,
       &mut self.changed_files,
@@ -42,12 +45,63 @@ impl WatcherResolver {
       specifier,
     );
 
-    self
+    let test_files = self
       .changed_files
       .iter()
       .cloned()
       .map(|url| url.path().into())
-      .collect()
+      .collect();
+
+    Some(test_files)
+  }
+
+  // Shortest import chain from `test_file` down to `file_path`,
+  // both ends included.
+  pub fn resolve_import_chain(
+    &self,
+    test_file: &Path,
+    file_path: &Path,
+  ) -> Option<Vec<PathBuf>> {
+    let root = ModuleSpecifier::from_file_path(test_file).ok()?;
+    let target = ModuleSpecifier::from_file_path(file_path).ok()?;
+
+    let mut importers: HashMap<ModuleSpecifier, ModuleSpecifier> =
+      HashMap::new();
+    let mut queue = VecDeque::from([root.clone()]);
+
+    while let Some(specifier) = queue.pop_front() {
+      if specifier == target {
+        let mut chain = vec![specifier.path().into()];
+        let mut current = &specifier;
+
+        while let Some(importer) = importers.get(current) {
+          chain.push(importer.path().into());
+          current = importer;
+        }
+
+        chain.reverse();
+        return Some(chain);
+      }
+
+      let Some(module) =
+        self.module_graph.get(&specifier).and_then(|m| m.js())
+      else {
+        continue;
+      };
+
+      for dependency in module.dependencies.values() {
+        let Some(dependency) = dependency.get_code() else {
+          continue;
+        };
+
+        if *dependency != root && !importers.contains_key(dependency) {
+          importers.insert(dependency.clone(), specifier.clone());
+          queue.push_back(dependency.clone());
+        }
+      }
+    }
+
+    None
   }
 
   fn resolve_dependencies(
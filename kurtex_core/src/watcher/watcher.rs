@@ -7,11 +7,14 @@ use deno_core::futures;
 use deno_core::futures::channel::mpsc;
 use deno_core::futures::channel::mpsc::channel;
 use deno_core::futures::SinkExt;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
+use log::debug;
+use notify::event::CreateKind;
 use notify::{EventKind, INotifyWatcher, RecursiveMode, Watcher};
 use tokio::time::timeout as recv_timeout;
 use tokio_stream::StreamExt;
 
+use crate::walk::GlobMatcher;
 use crate::AnyResult;
 
 pub const DEBOUNCER_CHANNEL_BUFFER: usize = 100;
@@ -20,6 +23,9 @@ pub const DEBOUNCER_CHANNEL_BUFFER: usize = 100;
 pub enum DebouncedEventKind {
   Update,
   Insert,
+  // A directory was created, it has to be watched when
+  // directories are watched one by one.
+  CreateDir,
 }
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
@@ -43,6 +49,7 @@ impl EventData {
 
 struct DebouncerDataInner {
   event_map: HashMap<PathBuf, EventData>,
+  created_dirs: Vec<PathBuf>,
   timeout: time::Duration,
   debounce_deadline: Option<time::Instant>,
 }
@@ -51,6 +58,7 @@ impl DebouncerDataInner {
   pub fn new(timeout: time::Duration) -> Self {
     DebouncerDataInner {
       event_map: HashMap::default(),
+      created_dirs: Vec::new(),
       timeout,
       debounce_deadline: None,
     }
@@ -70,6 +78,11 @@ impl DebouncerDataInner {
     let mut data_back = HashMap::with_capacity(self.event_map.len());
     self.debounce_deadline = None;
 
+    for path in self.created_dirs.drain(..) {
+      events_expired
+        .push(DebouncedEvent::new(path, DebouncedEventKind::CreateDir));
+    }
+
     for (path, event) in self.event_map.drain() {
       if event.update.elapsed() >= self.timeout {
         events_expired.push(DebouncedEvent::new(
@@ -128,6 +141,16 @@ impl DebouncerDataInner {
       self.debounce_deadline = Some(deadline_candidate)
     }
 
+    // Polling reports every creation as `CreateKind::Any`.
+    if let EventKind::Create(kind) = event.kind {
+      let created_dirs = event.paths.iter().filter(|path| {
+        kind == CreateKind::Folder
+          || (kind == CreateKind::Any && path.is_dir())
+      });
+
+      self.created_dirs.extend(created_dirs.cloned());
+    }
+
     if let EventKind::Modify(_) = event.kind {
       event.paths.iter().for_each(|path| {
         let has_tilde = path.to_string_lossy().ends_with("~");
@@ -159,6 +182,12 @@ impl DebouncerConfig {
 
     DebouncerConfig { timeout, inner: notify_config }
   }
+
+  // Only used by `PollWatcher`, other backends ignore it.
+  pub fn with_poll_interval(mut self, interval: time::Duration) -> Self {
+    self.inner = self.inner.with_poll_interval(interval);
+    self
+  }
 }
 
 impl DebouncedEvent {
@@ -191,6 +220,8 @@ impl DebounceEventHandler for mpsc::Sender<DebounceEventResult> {
 pub struct AsyncWatcherDebouncer<T: Watcher = INotifyWatcher> {
   pub(crate) watcher: T,
   inner_tx: mpsc::Sender<InnerEvent>,
+  // Paths registered with notify, to unwatch them on config reload.
+  watched_paths: HashSet<PathBuf>,
 }
 
 #[derive(Debug)]
@@ -205,18 +236,30 @@ impl<T: Watcher> AsyncWatcherDebouncer<T> {
     F: DebounceEventHandler,
     T: notify::Watcher,
   {
-    Self::new_inner(timeout, event_handler).unwrap()
+    Self::new_inner(DebouncerConfig::new(timeout), event_handler)
+      .unwrap()
+  }
+
+  pub fn with_config<F>(
+    debouncer_config: DebouncerConfig,
+    event_handler: F,
+  ) -> AnyResult<Self>
+  where
+    F: DebounceEventHandler,
+    T: notify::Watcher,
+  {
+    Self::new_inner(debouncer_config, event_handler)
   }
 
   fn new_inner<F>(
-    timeout: time::Duration,
+    debouncer_config: DebouncerConfig,
     mut event_handler: F,
   ) -> AnyResult<AsyncWatcherDebouncer<T>>
   where
     F: DebounceEventHandler,
     T: notify::Watcher,
   {
-    let debouncer_config = DebouncerConfig::new(timeout);
+    let timeout = debouncer_config.timeout;
     let (mut inner_tx, mut inner_rx) =
       channel::<InnerEvent>(DEBOUNCER_CHANNEL_BUFFER);
 
@@ -277,7 +320,11 @@ impl<T: Watcher> AsyncWatcherDebouncer<T> {
       debouncer_config.inner,
     )?;
 
-    let guard = AsyncWatcherDebouncer { watcher, inner_tx };
+    let guard = AsyncWatcherDebouncer {
+      watcher,
+      inner_tx,
+      watched_paths: HashSet::new(),
+    };
 
     Ok(guard)
   }
@@ -290,5 +337,63 @@ impl<T: Watcher> AsyncWatcherDebouncer<T> {
 
   pub fn watch(&mut self, path: &Path) {
     self.watcher.watch(path, RecursiveMode::Recursive).unwrap();
+    self.watched_paths.insert(path.to_path_buf());
+  }
+
+  // Watches every directory under `path` separately,
+  // so excluded directories are never registered with notify.
+  // Symlinked directories are skipped, they may form loops.
+  pub fn watch_filtered(
+    &mut self,
+    path: &Path,
+    excludes: &GlobMatcher,
+  ) -> AnyResult {
+    if excludes.is_empty() {
+      self.watch(path);
+      return Ok(());
+    }
+
+    if !self.watched_paths.insert(path.to_path_buf()) {
+      return Ok(());
+    }
+
+    self.watcher.watch(path, RecursiveMode::NonRecursive)?;
+
+    // Entries may vanish or be unreadable while walking, e.g. temporary
+    // directories, they are skipped instead of failing the watch.
+    for entry in std::fs::read_dir(path)? {
+      let entry = match entry {
+        Ok(entry) => entry,
+        Err(error) => {
+          debug!("Skipping an entry of {}: {}", path.display(), error);
+          continue;
+        }
+      };
+      let entry_path = entry.path();
+
+      let is_dir = match entry.file_type() {
+        Ok(file_type) => file_type.is_dir(),
+        Err(error) => {
+          debug!("Skipping {}: {}", entry_path.display(), error);
+          continue;
+        }
+      };
+
+      if is_dir && !excludes.is_dir_match(&entry_path) {
+        if let Err(error) = self.watch_filtered(&entry_path, excludes) {
+          debug!("Unable to watch {}: {}", entry_path.display(), error);
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  // Removes every watch, e.g. before watching with new excludes.
+  pub fn unwatch_all(&mut self) {
+    for path in self.watched_paths.drain() {
+      // Fails for directories that were removed in the meantime.
+      let _ = self.watcher.unwatch(&path);
+    }
   }
 }